@@ -1,13 +1,30 @@
 use crate::client_benchmark;
 use crate::client_benchmark::ClientBenchmark;
+use crate::client_collector;
+use crate::client_collector::Collector;
 use crate::client_config;
 use crate::client_config::ClientConfig;
 use crate::client_requests;
+use crate::client_requests::PostStatisticsError;
 use crate::client_statistics;
 use crate::client_statistics::ClientStatistics;
+use crate::client_status;
+use crate::client_status::ClientStatus;
+use crate::common_file;
+use crate::common_log;
+use crate::common_signal::{Reload, Shutdown};
+use crate::common_systemd;
 use crate::config::Config;
 use crate::de::De;
 use crate::info::Info;
+use log::{error, info, warn};
+use std::collections::VecDeque;
+use std::fs;
+use std::future::Future;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
@@ -16,22 +33,235 @@ pub async fn run(
     de: De,
     info: Info,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut client_config: ClientConfig = client_config::parse(&config)?;
+
+    common_log::configure(&config, client_config.log())?;
+
     let client_benchmark: ClientBenchmark = client_benchmark::run();
-    let client_config: ClientConfig = client_config::parse(config.etc_dir());
+    let client_status: Arc<Mutex<ClientStatus>> = Arc::new(Mutex::new(ClientStatus::default()));
+    let mut collectors: Vec<Box<dyn Collector>> = client_collector::get(&client_config);
     let mut client_statistics: ClientStatistics;
+    let queue_path: String = format!(
+        "{dir}{file}",
+        dir = config.data_dir(),
+        file = "client_queue.json"
+    );
+    let mut queue: VecDeque<String> = restore_queue(&queue_path, *client_config.queue_size());
+    let mut reload: Reload = Reload::new()?;
+    let mut shutdown: Shutdown = Shutdown::new()?;
     let sleep_duration: u64 = 10;
+
+    client_status
+        .lock()
+        .unwrap()
+        .set_benchmark(&client_benchmark);
+
+    if let Some(status_config) = client_config.status() {
+        let address: SocketAddr = (
+            Ipv4Addr::from_str(status_config.address())?,
+            *status_config.port(),
+        )
+            .into();
+
+        tokio::spawn(client_status::run(address, client_status.clone()));
+    }
+
+    common_systemd::notify("READY=1\nSTATUS=Collecting statistics");
+
     loop {
-        client_statistics = client_statistics::get();
-        client_requests::post_statistics(
-            &client_benchmark,
+        client_statistics = client_statistics::get(&client_config, &mut collectors);
+        client_status
+            .lock()
+            .unwrap()
+            .set_statistics(&client_statistics);
+
+        push_queue(
+            &mut queue,
+            *client_config.queue_size(),
+            client_requests::get_post_statistics_body(
+                &client_benchmark,
+                &client_statistics,
+                &config,
+                &de,
+            ),
+        );
+
+        flush_queue(
             &client_config,
-            &client_statistics,
+            &client_status,
             &config,
             &de,
             &info,
+            &mut queue,
         )
-        .await?;
-        thread::sleep(Duration::from_secs(sleep_duration));
+        .await;
+
+        common_systemd::notify(&format!(
+            "WATCHDOG=1\nSTATUS=Collecting statistics, {} queued",
+            queue.len()
+        ));
+
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(sleep_duration)) => {}
+            _ = reload.recv() => {
+                info!("Received SIGHUP, reloading configuration");
+
+                match client_config::parse(&config) {
+                    Ok(new_client_config) => {
+                        let (new_client_config, restart_required): (ClientConfig, Vec<&str>) =
+                            client_config.reload(new_client_config);
+
+                        match common_log::configure(&config, new_client_config.log()) {
+                            Ok(()) => {
+                                for name in restart_required {
+                                    warn!(
+                                        "Changing {} requires a restart, keeping the previous value",
+                                        name
+                                    );
+                                }
+
+                                collectors = client_collector::get(&new_client_config);
+                                client_config = new_client_config;
+
+                                info!("Configuration reloaded");
+                            }
+                            Err(x) => error!("Keeping the previous configuration: {}", x),
+                        }
+                    }
+                    Err(x) => error!("Keeping the previous configuration: {}", x),
+                }
+            }
+            signal = shutdown.recv() => {
+                let shutdown_timeout: Duration =
+                    Duration::from_secs(*client_config.shutdown_timeout());
+
+                info!("Received {}, flushing {} queued statistics", signal, queue.len());
+                common_systemd::notify("STOPPING=1\nSTATUS=Flushing queued statistics");
+
+                if tokio::time::timeout(
+                    shutdown_timeout,
+                    flush_queue(
+                        &client_config,
+                        &client_status,
+                        &config,
+                        &de,
+                        &info,
+                        &mut queue,
+                    ),
+                )
+                .await
+                .is_err()
+                {
+                    warn!(
+                        "Queue flush did not finish within {} seconds",
+                        shutdown_timeout.as_secs()
+                    );
+                }
+
+                save_queue(&queue_path, &queue);
+
+                return Ok(());
+            }
+        }
+    }
+}
+
+async fn flush_queue(
+    client_config: &ClientConfig,
+    client_status: &Arc<Mutex<ClientStatus>>,
+    config: &Config,
+    de: &De,
+    info: &Info,
+    queue: &mut VecDeque<String>,
+) {
+    flush(client_status, queue, |body: String| async move {
+        client_requests::post_statistics(&body, client_config, config, de, info).await
+    })
+    .await;
+}
+
+async fn flush<F, Fut>(
+    client_status: &Arc<Mutex<ClientStatus>>,
+    queue: &mut VecDeque<String>,
+    mut post_statistics: F,
+) where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<String, PostStatisticsError>>,
+{
+    while let Some(body) = queue.front() {
+        match post_statistics(body.to_string()).await {
+            Ok(fingerprint) => {
+                let mut client_status = client_status.lock().unwrap();
+                client_status.record_post_success();
+                client_status.set_server_public_key_fingerprint(fingerprint);
+                queue.pop_front();
+            }
+            Err(PostStatisticsError::Rejected(x)) => {
+                warn!("Dropping statistics rejected by the server: {}", x);
+                client_status.lock().unwrap().record_post_failure(x);
+                queue.pop_front();
+            }
+            Err(PostStatisticsError::Transient(x)) => {
+                warn!("Error while posting statistics: {}", x);
+                client_status.lock().unwrap().record_post_failure(x);
+                break;
+            }
+        }
+    }
+
+    client_status.lock().unwrap().set_queue_depth(queue.len());
+}
+
+fn push_queue(queue: &mut VecDeque<String>, queue_size: usize, body: String) {
+    while queue.len() >= queue_size.max(1) {
+        warn!("Queue is full, dropping the oldest statistics");
+        queue.pop_front();
+    }
+
+    queue.push_back(body);
+}
+
+fn restore_queue(queue_path: &str, queue_size: usize) -> VecDeque<String> {
+    if !Path::new(queue_path).exists() {
+        return VecDeque::new();
+    }
+
+    let mut queue: VecDeque<String> = match fs::read_to_string(queue_path)
+        .map_err(|x| x.to_string())
+        .and_then(|contents| serde_json::from_str(&contents).map_err(|x| x.to_string()))
+    {
+        Ok(queue) => queue,
+        Err(x) => {
+            warn!("Can not restore queued statistics: {}", x);
+            VecDeque::new()
+        }
+    };
+
+    if let Err(x) = fs::remove_file(queue_path) {
+        warn!("Can not remove queued statistics file: {}", x);
+    }
+
+    if queue.len() > queue_size.max(1) {
+        warn!(
+            "Dropping {} queued statistics over the queue size",
+            queue.len() - queue_size.max(1)
+        );
+        queue.drain(..queue.len() - queue_size.max(1));
+    }
+
+    info!("Restored {} queued statistics", queue.len());
+
+    queue
+}
+
+fn save_queue(queue_path: &str, queue: &VecDeque<String>) {
+    if queue.is_empty() {
+        return;
+    }
+
+    match common_file::write_atomic(queue_path, &serde_json::to_vec(queue).unwrap()) {
+        Ok(()) => info!("Saved {} queued statistics", queue.len()),
+        Err(x) => warn!("Can not save queued statistics: {}", x),
     }
 }
 
@@ -42,3 +272,50 @@ pub async fn run_load_simulator() -> Result<(), Box<dyn std::error::Error + Send
         thread::sleep(Duration::from_secs(sleep_duration));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn post_statistics(body: String) -> Result<String, PostStatisticsError> {
+        match body.as_str() {
+            "rejected" => Err(PostStatisticsError::Rejected(String::from("400"))),
+            "transient" => Err(PostStatisticsError::Transient(String::from("503"))),
+            _ => Ok(String::from("fingerprint")),
+        }
+    }
+
+    fn get_queue(bodies: &[&str]) -> VecDeque<String> {
+        bodies.iter().map(|body| body.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn flush_drops_rejected_statistics() {
+        let client_status: Arc<Mutex<ClientStatus>> = Arc::new(Mutex::new(ClientStatus::default()));
+        let mut queue: VecDeque<String> = get_queue(&["rejected", "accepted", "rejected"]);
+
+        flush(&client_status, &mut queue, post_statistics).await;
+
+        assert!(queue.is_empty());
+    }
+
+    #[tokio::test]
+    async fn flush_keeps_statistics_after_transient_error() {
+        let client_status: Arc<Mutex<ClientStatus>> = Arc::new(Mutex::new(ClientStatus::default()));
+        let mut queue: VecDeque<String> =
+            get_queue(&["accepted", "transient", "accepted", "rejected"]);
+
+        flush(&client_status, &mut queue, post_statistics).await;
+
+        assert_eq!(queue, get_queue(&["transient", "accepted", "rejected"]));
+    }
+
+    #[test]
+    fn push_queue_drops_oldest_statistics() {
+        let mut queue: VecDeque<String> = get_queue(&["1", "2", "3"]);
+
+        push_queue(&mut queue, 2, String::from("4"));
+
+        assert_eq!(queue, get_queue(&["3", "4"]));
+    }
+}
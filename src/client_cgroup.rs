@@ -0,0 +1,208 @@
+use crate::common_statistics_container::ContainerStatistics;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+const CGROUP_V1_MEMORY_UNLIMITED: u64 = 0x7FFF_FFFF_FFFF_F000;
+
+#[derive(Debug)]
+pub struct CgroupCounters {
+    cgroup_version: u8,
+    cpu_periods: u64,
+    cpu_throttled_periods: u64,
+    cpu_throttled_time: u64,
+    cpu_usage_time: Option<u64>,
+}
+
+impl CgroupCounters {
+    pub fn new(
+        cgroup_version: u8,
+        cpu_periods: u64,
+        cpu_throttled_periods: u64,
+        cpu_throttled_time: u64,
+        cpu_usage_time: Option<u64>,
+    ) -> Self {
+        Self {
+            cgroup_version,
+            cpu_periods,
+            cpu_throttled_periods,
+            cpu_throttled_time,
+            cpu_usage_time,
+        }
+    }
+}
+
+pub fn is_containerized() -> bool {
+    if Path::new("/.dockerenv").exists() || Path::new("/run/.containerenv").exists() {
+        return true;
+    }
+
+    match fs::read_to_string("/proc/1/cgroup") {
+        Ok(cgroup) => ["docker", "kubepods", "containerd", "libpod", "lxc"]
+            .iter()
+            .any(|runtime| cgroup.contains(runtime)),
+        Err(_) => false,
+    }
+}
+
+pub fn get_counters() -> Option<CgroupCounters> {
+    if Path::new(CGROUP_ROOT).join("cgroup.controllers").exists() {
+        let cgroup_dir: PathBuf = get_cgroup_dir("")?;
+        let cpu_stat: HashMap<String, u64> = read_key_values(&cgroup_dir.join("cpu.stat"));
+
+        Some(CgroupCounters::new(
+            2,
+            *cpu_stat.get("nr_periods").unwrap_or(&0),
+            *cpu_stat.get("nr_throttled").unwrap_or(&0),
+            *cpu_stat.get("throttled_usec").unwrap_or(&0),
+            cpu_stat.get("usage_usec").copied(),
+        ))
+    } else {
+        let cpu_dir: PathBuf = get_cgroup_dir("cpu")?;
+        let cpuacct_dir: Option<PathBuf> = get_cgroup_dir("cpuacct");
+        let cpu_stat: HashMap<String, u64> = read_key_values(&cpu_dir.join("cpu.stat"));
+        let cpu_usage_time: Option<u64> = cpuacct_dir
+            .and_then(|cpuacct_dir| read_u64(&cpuacct_dir.join("cpuacct.usage")))
+            .map(|cpu_usage_time| cpu_usage_time / 1000);
+
+        Some(CgroupCounters::new(
+            1,
+            *cpu_stat.get("nr_periods").unwrap_or(&0),
+            *cpu_stat.get("nr_throttled").unwrap_or(&0),
+            *cpu_stat.get("throttled_time").unwrap_or(&0) / 1000,
+            cpu_usage_time,
+        ))
+    }
+}
+
+pub fn get(before: &CgroupCounters, window: u64) -> Option<ContainerStatistics> {
+    let after: CgroupCounters = get_counters()?;
+    let cpu_quota: Option<f64>;
+    let memory_limit: Option<u64>;
+    let memory_usage: Option<u64>;
+
+    if after.cgroup_version == 2 {
+        let cgroup_dir: PathBuf = get_cgroup_dir("")?;
+        cpu_quota = fs::read_to_string(cgroup_dir.join("cpu.max"))
+            .ok()
+            .and_then(|cpu_max| {
+                let mut cpu_max = cpu_max.split_whitespace();
+                let quota: f64 = cpu_max.next()?.parse().ok()?;
+                let period: f64 = cpu_max.next()?.parse().ok()?;
+                Some(quota / period)
+            });
+        memory_limit = read_u64(&cgroup_dir.join("memory.max"));
+        memory_usage = read_u64(&cgroup_dir.join("memory.current"));
+    } else {
+        let cpu_dir: Option<PathBuf> = get_cgroup_dir("cpu");
+        let memory_dir: Option<PathBuf> = get_cgroup_dir("memory");
+        cpu_quota = cpu_dir.and_then(|cpu_dir| {
+            let quota: i64 = read_i64(&cpu_dir.join("cpu.cfs_quota_us"))?;
+            let period: i64 = read_i64(&cpu_dir.join("cpu.cfs_period_us"))?;
+
+            if quota <= 0 || period <= 0 {
+                return None;
+            }
+
+            Some(quota as f64 / period as f64)
+        });
+        memory_limit = memory_dir
+            .as_ref()
+            .and_then(|memory_dir| read_u64(&memory_dir.join("memory.limit_in_bytes")))
+            .filter(|memory_limit| *memory_limit < CGROUP_V1_MEMORY_UNLIMITED);
+        memory_usage = memory_dir
+            .as_ref()
+            .and_then(|memory_dir| read_u64(&memory_dir.join("memory.usage_in_bytes")));
+    }
+
+    let mut cpu_usage: Option<f32> = None;
+
+    if let (Some(usage_before), Some(usage_after)) = (before.cpu_usage_time, after.cpu_usage_time) {
+        let cpus: f64 = cpu_quota.unwrap_or(num_cpus::get() as f64);
+
+        if window > 0 && cpus > 0.0 {
+            let usage: f64 = usage_after.saturating_sub(usage_before) as f64
+                / (window as f64 * 1000.0 * cpus)
+                * 100.0;
+            cpu_usage = Some(usage.min(100.0) as f32);
+        }
+    }
+
+    Some(ContainerStatistics::new(
+        after.cgroup_version,
+        after.cpu_periods.saturating_sub(before.cpu_periods),
+        cpu_quota,
+        after
+            .cpu_throttled_periods
+            .saturating_sub(before.cpu_throttled_periods),
+        after
+            .cpu_throttled_time
+            .saturating_sub(before.cpu_throttled_time),
+        cpu_usage,
+        memory_limit,
+        memory_usage,
+    ))
+}
+
+fn get_cgroup_dir(controller: &str) -> Option<PathBuf> {
+    let cgroup: String = fs::read_to_string("/proc/self/cgroup").ok()?;
+
+    for line in cgroup.lines() {
+        let mut fields = line.splitn(3, ':');
+        let _hierarchy: &str = fields.next()?;
+        let controllers: &str = fields.next()?;
+        let path: &str = fields.next()?.trim_start_matches('/');
+        let controller_dir: PathBuf;
+
+        if controller.is_empty() {
+            if !controllers.is_empty() {
+                continue;
+            }
+
+            controller_dir = PathBuf::from(CGROUP_ROOT);
+        } else if controllers.split(',').any(|c| c == controller) {
+            controller_dir = Path::new(CGROUP_ROOT).join(controllers);
+        } else {
+            continue;
+        }
+
+        let cgroup_dir: PathBuf = controller_dir.join(path);
+
+        if cgroup_dir.exists() {
+            return Some(cgroup_dir);
+        }
+
+        if controller_dir.exists() {
+            return Some(controller_dir);
+        }
+    }
+
+    None
+}
+
+fn read_i64(path: &Path) -> Option<i64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn read_key_values(path: &Path) -> HashMap<String, u64> {
+    let mut key_values: HashMap<String, u64> = HashMap::new();
+
+    if let Ok(contents) = fs::read_to_string(path) {
+        for line in contents.lines() {
+            let mut fields = line.split_whitespace();
+
+            if let (Some(key), Some(value)) = (fields.next(), fields.next()) {
+                if let Ok(value) = value.parse() {
+                    key_values.insert(key.to_string(), value);
+                }
+            }
+        }
+    }
+
+    key_values
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
@@ -0,0 +1,165 @@
+use crate::client_collector_process::ProcessCollector;
+use crate::client_config::ClientConfig;
+use crate::common_metric::{MetricValue, Metrics};
+use log::warn;
+use systemstat::{CPULoad, DelayedMeasurement, Memory, Platform, System};
+
+pub trait Collector: Send {
+    fn name(&self) -> &'static str;
+
+    fn start(&mut self, _system: &System) {}
+
+    fn collect(&mut self, system: &System, metrics: &mut Metrics);
+}
+
+#[derive(Default)]
+pub struct CpuCollector {
+    cpu_load: Option<DelayedMeasurement<CPULoad>>,
+}
+
+impl Collector for CpuCollector {
+    fn name(&self) -> &'static str {
+        "cpu"
+    }
+
+    fn start(&mut self, system: &System) {
+        match system.cpu_load_aggregate() {
+            Ok(cpu_load) => self.cpu_load = Some(cpu_load),
+            Err(x) => warn!("CPU load: error: {}", x),
+        }
+    }
+
+    fn collect(&mut self, _system: &System, metrics: &mut Metrics) {
+        insert_integer(metrics, "cpu.count", num_cpus::get() as u64);
+
+        if let Some(cpu_load) = self.cpu_load.take() {
+            match cpu_load.done() {
+                Ok(cpu) => {
+                    insert_percent(metrics, "cpu.idle", cpu.idle);
+                    insert_percent(metrics, "cpu.interrupt", cpu.interrupt);
+                    insert_percent(metrics, "cpu.nice", cpu.nice);
+                    insert_percent(metrics, "cpu.system", cpu.system);
+                    insert_percent(metrics, "cpu.user", cpu.user);
+                }
+                Err(x) => warn!("CPU load: error: {}", x),
+            }
+        }
+    }
+}
+
+pub struct LoadCollector;
+
+impl Collector for LoadCollector {
+    fn name(&self) -> &'static str {
+        "load"
+    }
+
+    fn collect(&mut self, system: &System, metrics: &mut Metrics) {
+        match system.load_average() {
+            Ok(load_average) => {
+                insert_float(metrics, "load.fifteen", load_average.fifteen as f64);
+                insert_float(metrics, "load.five", load_average.five as f64);
+                insert_float(metrics, "load.one", load_average.one as f64);
+            }
+            Err(x) => warn!("Load average: error: {}", x),
+        }
+    }
+}
+
+pub struct MemoryCollector;
+
+impl Collector for MemoryCollector {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn collect(&mut self, system: &System, metrics: &mut Metrics) {
+        match system.memory() {
+            Ok(memory) => {
+                let mem_free: u64 = memory.free.as_u64();
+                let mem_total: u64 = memory.total.as_u64();
+                let mem_available: u64 = get_mem_available(&memory).unwrap_or(mem_free);
+
+                insert_integer(metrics, "memory.available", mem_available);
+                insert_integer(metrics, "memory.free", mem_free);
+                insert_integer(metrics, "memory.total", mem_total);
+                insert_float(
+                    metrics,
+                    "memory.usage",
+                    (mem_free as f64 / mem_total as f64) as f32 as f64,
+                );
+                insert_float(
+                    metrics,
+                    "memory.used_percent",
+                    mem_total.saturating_sub(mem_available) as f64 / mem_total as f64 * 100.0,
+                );
+            }
+            Err(x) => warn!("Memory: error: {}", x),
+        }
+    }
+}
+
+pub struct UptimeCollector;
+
+impl Collector for UptimeCollector {
+    fn name(&self) -> &'static str {
+        "uptime"
+    }
+
+    fn collect(&mut self, system: &System, metrics: &mut Metrics) {
+        match system.uptime() {
+            Ok(uptime) => insert_float(metrics, "uptime", uptime.as_secs_f64()),
+            Err(x) => warn!("Uptime: error: {}", x),
+        }
+    }
+}
+
+pub fn get(client_config: &ClientConfig) -> Vec<Box<dyn Collector>> {
+    let mut collectors: Vec<Box<dyn Collector>> = vec![];
+
+    for name in client_config.collectors() {
+        if collectors.iter().any(|collector| collector.name() == name) {
+            continue;
+        }
+
+        match name.as_str() {
+            "cpu" => collectors.push(Box::new(CpuCollector::default())),
+            "load" => collectors.push(Box::new(LoadCollector)),
+            "memory" => collectors.push(Box::new(MemoryCollector)),
+            "process" => collectors.push(Box::new(ProcessCollector::new(
+                *client_config.process_top(),
+                client_config.workloads().to_vec(),
+            ))),
+            "uptime" => collectors.push(Box::new(UptimeCollector)),
+            _ => warn!("Unknown collector = {}", name),
+        }
+    }
+
+    collectors
+}
+
+#[cfg(target_os = "linux")]
+fn get_mem_available(memory: &Memory) -> Option<u64> {
+    memory
+        .platform_memory
+        .meminfo
+        .get("MemAvailable")
+        .map(|mem_available| mem_available.as_u64())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn get_mem_available(_memory: &Memory) -> Option<u64> {
+    None
+}
+
+pub fn insert_integer(metrics: &mut Metrics, name: &str, value: u64) {
+    metrics.insert(name.to_string(), MetricValue::Integer(value as i64));
+}
+
+pub fn insert_float(metrics: &mut Metrics, name: &str, value: f64) {
+    metrics.insert(name.to_string(), MetricValue::Float(value));
+}
+
+fn insert_percent(metrics: &mut Metrics, name: &str, value: f32) {
+    insert_float(metrics, name, (value * 100.0) as f64);
+}
@@ -0,0 +1,155 @@
+use crate::client_collector;
+use crate::client_collector::Collector;
+use crate::common_metric::{MetricValue, Metrics};
+use std::collections::HashMap;
+use std::fs;
+use std::time::Instant;
+use systemstat::System;
+
+const USER_HZ: f64 = 100.0;
+
+#[derive(Clone, Debug)]
+struct ProcessTime {
+    name: String,
+    time: u64,
+}
+
+pub struct ProcessCollector {
+    process_times: HashMap<u32, ProcessTime>,
+    start: Option<Instant>,
+    top: usize,
+    workloads: Vec<String>,
+}
+
+impl ProcessCollector {
+    pub fn new(top: usize, workloads: Vec<String>) -> Self {
+        Self {
+            process_times: HashMap::new(),
+            start: None,
+            top,
+            workloads,
+        }
+    }
+
+    fn is_workload(&self, name: &str) -> bool {
+        self.workloads
+            .iter()
+            .any(|workload| workload == name || (name.len() == 15 && workload.starts_with(name)))
+    }
+}
+
+impl Collector for ProcessCollector {
+    fn name(&self) -> &'static str {
+        "process"
+    }
+
+    fn start(&mut self, _system: &System) {
+        self.process_times = get_process_times();
+        self.start = Some(Instant::now());
+    }
+
+    fn collect(&mut self, _system: &System, metrics: &mut Metrics) {
+        let start: Instant = match self.start.take() {
+            Some(start) => start,
+            None => return,
+        };
+        let window: f64 = start.elapsed().as_secs_f64();
+        let cpus: f64 = num_cpus::get() as f64;
+
+        if window <= 0.0 {
+            return;
+        }
+
+        let mut processes: Vec<(u32, String, f64)> = vec![];
+        let mut workload_cpu: f64 = 0.0;
+
+        for (pid, process_time) in get_process_times() {
+            let time_before: u64 = match self.process_times.get(&pid) {
+                Some(before) if before.name == process_time.name => before.time,
+                _ => continue,
+            };
+            let cpu: f64 =
+                process_time.time.saturating_sub(time_before) as f64 / USER_HZ / (window * cpus)
+                    * 100.0;
+
+            if self.is_workload(&process_time.name) {
+                workload_cpu += cpu;
+            }
+
+            processes.push((pid, process_time.name, cpu));
+        }
+
+        processes.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
+
+        for (index, (pid, name, cpu)) in processes.iter().take(self.top).enumerate() {
+            let prefix: String = format!("process.{}", index);
+
+            client_collector::insert_float(metrics, &format!("{}.cpu", prefix), *cpu);
+            client_collector::insert_integer(metrics, &format!("{}.pid", prefix), *pid as u64);
+            client_collector::insert_integer(
+                metrics,
+                &format!("{}.rss", prefix),
+                get_process_rss(*pid).unwrap_or(0),
+            );
+            metrics.insert(
+                format!("{}.name", prefix),
+                MetricValue::Text(name.to_string()),
+            );
+            metrics.insert(
+                format!("{}.workload", prefix),
+                MetricValue::Bool(self.is_workload(name)),
+            );
+        }
+
+        client_collector::insert_float(metrics, "process.workload_cpu", workload_cpu.min(100.0));
+    }
+}
+
+fn get_process_rss(pid: u32) -> Option<u64> {
+    let status: String = fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+
+    for line in status.lines() {
+        if let Some(rss) = line.strip_prefix("VmRSS:") {
+            let rss_kilobytes: u64 = rss.trim().trim_end_matches("kB").trim().parse().ok()?;
+            return Some(rss_kilobytes * 1024);
+        }
+    }
+
+    None
+}
+
+fn get_process_times() -> HashMap<u32, ProcessTime> {
+    let mut process_times: HashMap<u32, ProcessTime> = HashMap::new();
+    let entries = match fs::read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(_) => return process_times,
+    };
+
+    for entry in entries.flatten() {
+        let pid: u32 = match entry.file_name().to_string_lossy().parse() {
+            Ok(pid) => pid,
+            Err(_) => continue,
+        };
+
+        if let Some(process_time) = get_process_time(pid) {
+            process_times.insert(pid, process_time);
+        }
+    }
+
+    process_times
+}
+
+fn get_process_time(pid: u32) -> Option<ProcessTime> {
+    let stat: String = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let name_start: usize = stat.find('(')? + 1;
+    let name_end: usize = stat.rfind(')')?;
+    let name: String = stat.get(name_start..name_end)?.to_string();
+    let fields: Vec<&str> = stat.get(name_end + 1..)?.split_whitespace().collect();
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+
+    Some(ProcessTime {
+        name,
+        time: utime + stime,
+    })
+}
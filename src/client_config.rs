@@ -1,26 +1,157 @@
-use serde_derive::Deserialize;
-use std::fs;
+use crate::common_config;
+use crate::common_config::ConfigSources;
+use crate::common_log::LogConfig;
+use crate::config::Config;
+use hyper::Uri;
+use serde_derive::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
+use std::str::FromStr;
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ClientStatusConfig {
+    address: String,
+    port: u16,
+}
+
+impl ClientStatusConfig {
+    pub fn address(&self) -> &String {
+        &self.address
+    }
+
+    pub fn port(&self) -> &u16 {
+        &self.port
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct ClientConfig {
+    #[serde(default = "default_collectors")]
+    collectors: Vec<String>,
     endpoint: String,
+    #[serde(default)]
+    log: LogConfig,
+    #[serde(default)]
+    network_interfaces: Option<Vec<String>>,
+    #[serde(default = "default_process_top")]
+    process_top: usize,
+    #[serde(default = "default_queue_size")]
+    queue_size: usize,
+    #[serde(default = "default_shutdown_timeout")]
+    shutdown_timeout: u64,
+    #[serde(default)]
+    status: Option<ClientStatusConfig>,
+    #[serde(default)]
+    workloads: Vec<String>,
 }
 
 impl ClientConfig {
+    pub fn collectors(&self) -> &Vec<String> {
+        &self.collectors
+    }
+
     pub fn endpoint(&self) -> &String {
         &self.endpoint
     }
+
+    pub fn log(&self) -> &LogConfig {
+        &self.log
+    }
+
+    pub fn network_interfaces(&self) -> &Option<Vec<String>> {
+        &self.network_interfaces
+    }
+
+    pub fn process_top(&self) -> &usize {
+        &self.process_top
+    }
+
+    pub fn queue_size(&self) -> &usize {
+        &self.queue_size
+    }
+
+    pub fn shutdown_timeout(&self) -> &u64 {
+        &self.shutdown_timeout
+    }
+
+    pub fn status(&self) -> &Option<ClientStatusConfig> {
+        &self.status
+    }
+
+    pub fn workloads(&self) -> &Vec<String> {
+        &self.workloads
+    }
+
+    pub fn reload(&self, client_config: ClientConfig) -> (ClientConfig, Vec<&'static str>) {
+        let mut client_config: ClientConfig = client_config;
+        let mut restart_required: Vec<&'static str> = vec![];
+
+        if client_config.status != self.status {
+            client_config.status = self.status.clone();
+            restart_required.push("status");
+        }
+
+        (client_config, restart_required)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let uri: Uri = self
+            .endpoint
+            .parse()
+            .map_err(|x| format!("endpoint: {} is not valid: {}", self.endpoint, x))?;
+
+        if uri.scheme_str() != Some("http") {
+            return Err(format!("endpoint: {} is not an http URL", self.endpoint));
+        }
+
+        if let Some(status) = &self.status {
+            Ipv4Addr::from_str(status.address()).map_err(|x| format!("status.address: {}", x))?;
+        }
+
+        self.log.validate()
+    }
 }
 
-pub fn parse(etc_dir: &str) -> ClientConfig {
+fn default_collectors() -> Vec<String> {
+    vec![
+        "cpu".to_string(),
+        "load".to_string(),
+        "memory".to_string(),
+        "uptime".to_string(),
+    ]
+}
+
+fn default_process_top() -> usize {
+    5
+}
+
+fn default_queue_size() -> usize {
+    360
+}
+
+fn default_shutdown_timeout() -> u64 {
+    10
+}
+
+fn load(config: &Config) -> Result<(ClientConfig, ConfigSources), String> {
     let config_file_name: String = format!(
         "{etc_dir}/{config_file}",
-        etc_dir = etc_dir,
+        etc_dir = config.etc_dir(),
         config_file = "auditor_client.toml"
     );
-    let config_file_contents: String =
-        fs::read_to_string(&config_file_name).expect("Can not open configuration file.");
-    let config_file_parameters: ClientConfig = toml::from_str(&config_file_contents).unwrap();
+    let (config_parameters, sources): (ClientConfig, ConfigSources) =
+        common_config::load(&config_file_name, config.overrides())?;
+
+    common_config::check(config_parameters.validate(), &sources)?;
+
+    Ok((config_parameters, sources))
+}
+
+pub fn parse(config: &Config) -> Result<ClientConfig, String> {
+    load(config).map(|(config_parameters, _)| config_parameters)
+}
+
+pub fn print(config: &Config) -> Result<(), String> {
+    let (config_parameters, sources): (ClientConfig, ConfigSources) = load(config)?;
 
-    config_file_parameters
+    common_config::print(&config_parameters, &sources)
 }
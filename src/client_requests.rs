@@ -6,33 +6,61 @@ use crate::config::Config;
 use crate::de;
 use crate::de::De;
 use crate::info::Info;
+use crate::request_post_statistics;
 use crate::request_post_statistics::RequestPostStatistics;
 use crate::response_post_statistics::ResponsePostStatistics;
-use hyper::{Body, Client, Method, Request};
-use log::warn;
+use hyper::{Body, Client, Method, Request, Response, StatusCode};
+use log::info;
+use std::fmt;
+use std::fs;
+use std::path::Path;
 
-pub async fn post_statistics(
+#[derive(Debug)]
+pub enum PostStatisticsError {
+    Rejected(String),
+    Transient(String),
+}
+
+impl fmt::Display for PostStatisticsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PostStatisticsError::Rejected(x) => write!(f, "rejected: {}", x),
+            PostStatisticsError::Transient(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+pub fn get_post_statistics_body(
     client_benchmark: &ClientBenchmark,
-    client_config: &ClientConfig,
     client_statistics: &ClientStatistics,
     config: &Config,
     de: &De,
-    info: &Info,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+) -> String {
     let request_post_statistics: RequestPostStatistics = RequestPostStatistics::new(
+        *client_statistics.collected_at(),
+        client_statistics.container().clone(),
+        *client_statistics.containerized(),
+        client_statistics.cpu_cores().to_vec(),
         *client_statistics.cpu_count(),
         *client_statistics.cpu_idle(),
         *client_statistics.cpu_interrupt(),
         *client_statistics.cpu_nice(),
         *client_statistics.cpu_system(),
         *client_statistics.cpu_user(),
+        client_statistics.disk_io().to_vec(),
+        client_statistics.disk_mounts().to_vec(),
         config.host_id().to_string(),
+        *client_statistics.mem_available(),
         *client_statistics.mem_free(),
         *client_statistics.mem_usage(),
         *client_statistics.mem_total(),
+        *client_statistics.mem_used_percent(),
+        client_statistics.metrics().clone(),
+        client_statistics.network_interfaces().to_vec(),
         *client_benchmark.mt_2_result(),
         *client_benchmark.mt_4_result(),
         *client_benchmark.mt_8_result(),
+        request_post_statistics::PROTOCOL_VERSION,
         de.public_key_string(),
         *client_benchmark.st_result(),
         *client_statistics.sys_load_average_fifteen(),
@@ -40,43 +68,112 @@ pub async fn post_statistics(
         *client_statistics.sys_load_average_one(),
         *client_statistics.sys_uptime(),
     );
-    let body: String = serde_json::to_string(&request_post_statistics).unwrap();
-    let signature: String = de.sign(&body);
+
+    serde_json::to_string(&request_post_statistics).unwrap()
+}
+
+pub async fn post_statistics(
+    body: &str,
+    client_config: &ClientConfig,
+    config: &Config,
+    de: &De,
+    info: &Info,
+) -> Result<String, PostStatisticsError> {
+    let signature: String = de.sign(body);
     let client = Client::new();
-    let request = Request::builder()
+    let mut request = Request::builder()
         .method(Method::POST)
         .uri(format!("{}/statistics", client_config.endpoint()))
         .header("content-type", "application/json")
         .header("signature", signature)
-        .header("user-agent", format!("{}/{}", info.name(), info.version()))
-        .body(Body::from(body))
+        .header("user-agent", format!("{}/{}", info.name(), info.version()));
+
+    if let Some(previous_signature) = de.sign_previous(body) {
+        request = request.header("previous-signature", previous_signature);
+    }
+
+    let request = request
+        .body(Body::from(body.to_string()))
         .expect("request builder");
-    let future = client.request(request).await;
-
-    match future {
-        Ok(future) => {
-            if future.status() == 201 {
-                let signature: String = common_request::get_signature_from_response(&future);
-                let body_bytes = hyper::body::to_bytes(future.into_body()).await?;
-                let body: String = String::from_utf8(body_bytes.to_vec()).unwrap();
-                let response_post_statistics: ResponsePostStatistics =
-                    serde_json::from_str(&body).unwrap();
-                let signature_correct: bool =
-                    de::check_sign(&body, response_post_statistics.public_key(), &signature);
-
-                if !signature_correct {
-                    warn!(
-                        "Incorrect signature for host = {}",
-                        response_post_statistics.host_id()
-                    );
-                }
-            } else {
-                warn!("Server error = {}", future.status());
-            }
-        }
-        Err(_) => {
-            warn!("Error while connecting to the server");
-        }
+    let response = client
+        .request(request)
+        .await
+        .map_err(|x| PostStatisticsError::Transient(x.to_string()))?;
+    let status: StatusCode = response.status();
+
+    if status.is_client_error()
+        && status != StatusCode::REQUEST_TIMEOUT
+        && status != StatusCode::TOO_MANY_REQUESTS
+    {
+        return Err(PostStatisticsError::Rejected(format!(
+            "Server error = {}",
+            status
+        )));
+    }
+
+    if status != StatusCode::CREATED {
+        return Err(PostStatisticsError::Transient(format!(
+            "Server error = {}",
+            status
+        )));
+    }
+
+    get_server_fingerprint(config, response)
+        .await
+        .map_err(|x| PostStatisticsError::Transient(x.to_string()))
+}
+
+async fn get_server_fingerprint(
+    config: &Config,
+    response: Response<Body>,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let signature: String = common_request::get_signature_from_response(&response);
+    let body_bytes = hyper::body::to_bytes(response.into_body()).await?;
+    let body: String = String::from_utf8(body_bytes.to_vec())?;
+    let response_post_statistics: ResponsePostStatistics = serde_json::from_str(&body)?;
+
+    if !de::check_sign(&body, response_post_statistics.public_key(), &signature) {
+        return Err(format!(
+            "Incorrect signature for host = {}",
+            response_post_statistics.host_id()
+        )
+        .into());
+    }
+
+    check_server_key(config, response_post_statistics.public_key())?;
+
+    Ok(de::fingerprint(response_post_statistics.public_key()))
+}
+
+fn check_server_key(
+    config: &Config,
+    public_key: &str,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let server_key_path: String = format!(
+        "{dir}{file}",
+        dir = config.data_dir(),
+        file = "server_key.pub"
+    );
+
+    if !Path::new(&server_key_path).exists() {
+        fs::write(&server_key_path, public_key)?;
+        info!(
+            "Pinned server key = {} on first use",
+            de::fingerprint(public_key)
+        );
+
+        return Ok(());
+    }
+
+    let pinned_key: String = fs::read_to_string(&server_key_path)?;
+
+    if pinned_key != public_key {
+        return Err(format!(
+            "Server key = {} does not match the pinned key = {}",
+            de::fingerprint(public_key),
+            de::fingerprint(&pinned_key)
+        )
+        .into());
     }
 
     Ok(())
@@ -1,50 +1,100 @@
+use crate::client_cgroup;
+use crate::client_cgroup::CgroupCounters;
+use crate::client_collector;
+use crate::client_collector::Collector;
+use crate::client_config::ClientConfig;
+use crate::common_metric;
+use crate::common_metric::Metrics;
+use crate::common_statistics_container::ContainerStatistics;
+use crate::common_statistics_cpu::CpuCoreStatistics;
+use crate::common_statistics_disk::{DiskIoStatistics, DiskMountStatistics};
+use crate::common_statistics_network::NetworkInterfaceStatistics;
+use log::warn;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
 use std::thread;
-use std::time::Duration;
-use systemstat::{CPULoad, Platform, System};
+use std::time::{Duration, Instant, SystemTime};
+use systemstat::{
+    BlockDeviceStats, CPULoad, DelayedMeasurement, Filesystem, NetworkStats, Platform, System,
+};
 
-#[derive(Debug)]
+const DISK_SECTOR_SIZE: u64 = 512;
+
+#[derive(Clone, Debug, Serialize)]
 pub struct ClientStatistics {
-    cpu_count: u16,
-    cpu_idle: f32,
-    cpu_interrupt: f32,
-    cpu_nice: f32,
-    cpu_system: f32,
-    cpu_user: f32,
-    mem_free: u64,
-    mem_usage: f32,
-    mem_total: u64,
-    sys_load_average_fifteen: f32,
-    sys_load_average_five: f32,
-    sys_load_average_one: f32,
-    sys_uptime: f64,
+    collected_at: u64,
+    container: Option<ContainerStatistics>,
+    containerized: bool,
+    cpu_cores: Vec<CpuCoreStatistics>,
+    cpu_count: Option<u16>,
+    cpu_idle: Option<f32>,
+    cpu_interrupt: Option<f32>,
+    cpu_nice: Option<f32>,
+    cpu_system: Option<f32>,
+    cpu_user: Option<f32>,
+    disk_io: Vec<DiskIoStatistics>,
+    disk_mounts: Vec<DiskMountStatistics>,
+    mem_available: Option<u64>,
+    mem_free: Option<u64>,
+    mem_usage: Option<f32>,
+    mem_total: Option<u64>,
+    mem_used_percent: Option<f32>,
+    metrics: Metrics,
+    network_interfaces: Vec<NetworkInterfaceStatistics>,
+    sys_load_average_fifteen: Option<f32>,
+    sys_load_average_five: Option<f32>,
+    sys_load_average_one: Option<f32>,
+    sys_uptime: Option<f64>,
 }
 
 impl ClientStatistics {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        cpu_count: u16,
-        cpu_idle: f32,
-        cpu_interrupt: f32,
-        cpu_nice: f32,
-        cpu_system: f32,
-        cpu_user: f32,
-        mem_free: u64,
-        mem_usage: f32,
-        mem_total: u64,
-        sys_load_average_fifteen: f32,
-        sys_load_average_five: f32,
-        sys_load_average_one: f32,
-        sys_uptime: f64,
+        collected_at: u64,
+        container: Option<ContainerStatistics>,
+        containerized: bool,
+        cpu_cores: Vec<CpuCoreStatistics>,
+        cpu_count: Option<u16>,
+        cpu_idle: Option<f32>,
+        cpu_interrupt: Option<f32>,
+        cpu_nice: Option<f32>,
+        cpu_system: Option<f32>,
+        cpu_user: Option<f32>,
+        disk_io: Vec<DiskIoStatistics>,
+        disk_mounts: Vec<DiskMountStatistics>,
+        mem_available: Option<u64>,
+        mem_free: Option<u64>,
+        mem_usage: Option<f32>,
+        mem_total: Option<u64>,
+        mem_used_percent: Option<f32>,
+        metrics: Metrics,
+        network_interfaces: Vec<NetworkInterfaceStatistics>,
+        sys_load_average_fifteen: Option<f32>,
+        sys_load_average_five: Option<f32>,
+        sys_load_average_one: Option<f32>,
+        sys_uptime: Option<f64>,
     ) -> Self {
         Self {
+            collected_at,
+            container,
+            containerized,
+            cpu_cores,
             cpu_count,
             cpu_idle,
             cpu_interrupt,
             cpu_nice,
             cpu_system,
             cpu_user,
+            disk_io,
+            disk_mounts,
+            mem_available,
             mem_free,
             mem_usage,
             mem_total,
+            mem_used_percent,
+            metrics,
+            network_interfaces,
             sys_load_average_fifteen,
             sys_load_average_five,
             sys_load_average_one,
@@ -52,127 +102,455 @@ impl ClientStatistics {
         }
     }
 
-    pub fn cpu_count(&self) -> &u16 {
+    pub fn collected_at(&self) -> &u64 {
+        &self.collected_at
+    }
+
+    pub fn container(&self) -> &Option<ContainerStatistics> {
+        &self.container
+    }
+
+    pub fn containerized(&self) -> &bool {
+        &self.containerized
+    }
+
+    pub fn cpu_cores(&self) -> &Vec<CpuCoreStatistics> {
+        &self.cpu_cores
+    }
+
+    pub fn cpu_count(&self) -> &Option<u16> {
         &self.cpu_count
     }
 
-    pub fn cpu_idle(&self) -> &f32 {
+    pub fn cpu_idle(&self) -> &Option<f32> {
         &self.cpu_idle
     }
 
-    pub fn cpu_interrupt(&self) -> &f32 {
+    pub fn cpu_interrupt(&self) -> &Option<f32> {
         &self.cpu_interrupt
     }
 
-    pub fn cpu_nice(&self) -> &f32 {
+    pub fn cpu_nice(&self) -> &Option<f32> {
         &self.cpu_nice
     }
 
-    pub fn cpu_system(&self) -> &f32 {
+    pub fn cpu_system(&self) -> &Option<f32> {
         &self.cpu_system
     }
 
-    pub fn cpu_user(&self) -> &f32 {
+    pub fn cpu_user(&self) -> &Option<f32> {
         &self.cpu_user
     }
 
-    pub fn mem_free(&self) -> &u64 {
+    pub fn disk_io(&self) -> &Vec<DiskIoStatistics> {
+        &self.disk_io
+    }
+
+    pub fn disk_mounts(&self) -> &Vec<DiskMountStatistics> {
+        &self.disk_mounts
+    }
+
+    pub fn mem_available(&self) -> &Option<u64> {
+        &self.mem_available
+    }
+
+    pub fn mem_free(&self) -> &Option<u64> {
         &self.mem_free
     }
 
-    pub fn mem_usage(&self) -> &f32 {
+    pub fn mem_usage(&self) -> &Option<f32> {
         &self.mem_usage
     }
 
-    pub fn mem_total(&self) -> &u64 {
+    pub fn mem_total(&self) -> &Option<u64> {
         &self.mem_total
     }
 
-    pub fn sys_load_average_fifteen(&self) -> &f32 {
+    pub fn mem_used_percent(&self) -> &Option<f32> {
+        &self.mem_used_percent
+    }
+
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    pub fn network_interfaces(&self) -> &Vec<NetworkInterfaceStatistics> {
+        &self.network_interfaces
+    }
+
+    pub fn sys_load_average_fifteen(&self) -> &Option<f32> {
         &self.sys_load_average_fifteen
     }
 
-    pub fn sys_load_average_five(&self) -> &f32 {
+    pub fn sys_load_average_five(&self) -> &Option<f32> {
         &self.sys_load_average_five
     }
 
-    pub fn sys_load_average_one(&self) -> &f32 {
+    pub fn sys_load_average_one(&self) -> &Option<f32> {
         &self.sys_load_average_one
     }
 
-    pub fn sys_uptime(&self) -> &f64 {
+    pub fn sys_uptime(&self) -> &Option<f64> {
         &self.sys_uptime
     }
 }
 
-pub fn get() -> ClientStatistics {
+pub fn get(
+    client_config: &ClientConfig,
+    collectors: &mut [Box<dyn Collector>],
+) -> ClientStatistics {
     let system = System::new();
-    let cpu_count: u16 = num_cpus::get() as u16;
-    let mut cpu_idle: f32 = 0.0;
-    let mut cpu_interrupt: f32 = 0.0;
-    let mut cpu_nice: f32 = 0.0;
-    let mut cpu_system: f32 = 0.0;
-    let mut cpu_user: f32 = 0.0;
-    let mut mem_free: u64 = 0;
-    let mut mem_usage: f32 = 0.0;
-    let mut mem_total: u64 = 0;
-    let mut sys_load_average_fifteen: f32 = 0.0;
-    let mut sys_load_average_five: f32 = 0.0;
-    let mut sys_load_average_one: f32 = 0.0;
-    let mut sys_uptime: f64 = 0.0;
-
-    match system.cpu_load_aggregate() {
-        Ok(cpu) => {
-            thread::sleep(Duration::from_secs(1));
-            let cpu: CPULoad = cpu.done().unwrap();
-
-            cpu_idle = cpu.idle * 100.0;
-            cpu_interrupt = cpu.interrupt * 100.0;
-            cpu_nice = cpu.nice * 100.0;
-            cpu_system = cpu.system * 100.0;
-            cpu_user = cpu.user * 100.0;
+    let mut container: Option<ContainerStatistics> = None;
+    let containerized: bool = client_cgroup::is_containerized();
+    let mut cpu_cores: Vec<CpuCoreStatistics> = vec![];
+    let mut disk_io: Vec<DiskIoStatistics> = vec![];
+    let mut disk_mounts: Vec<DiskMountStatistics> = vec![];
+    let mut metrics: Metrics = Metrics::new();
+    let mut network_interfaces: Vec<NetworkInterfaceStatistics> = vec![];
+
+    let block_devices_before: Option<BTreeMap<String, BlockDeviceStats>> =
+        match system.block_device_statistics() {
+            Ok(block_devices) => Some(block_devices),
+            Err(x) => {
+                warn!("Block devices: error: {}", x);
+                None
+            }
+        };
+    let network_interfaces_before: Option<BTreeMap<String, NetworkStats>> = match system.networks()
+    {
+        Ok(networks) => Some(get_network_stats(
+            &system,
+            networks.keys(),
+            client_config.network_interfaces(),
+        )),
+        Err(x) => {
+            warn!("Networks: error: {}", x);
+            None
+        }
+    };
+    let cpu_cores_load: Option<DelayedMeasurement<Vec<CPULoad>>> = match system.cpu_load() {
+        Ok(cpu_cores_load) => Some(cpu_cores_load),
+        Err(x) => {
+            warn!("CPU cores load: error: {}", x);
+            None
         }
-        Err(x) => println!("\nCPU load: error: {}", x),
+    };
+    let cgroup_counters_before: Option<CgroupCounters> = if containerized {
+        client_cgroup::get_counters()
+    } else {
+        None
+    };
+    let window_start: Instant = Instant::now();
+
+    for collector in collectors.iter_mut() {
+        collector.start(&system);
+    }
+
+    thread::sleep(Duration::from_secs(1));
+
+    let window: u64 = window_start.elapsed().as_millis() as u64;
+
+    for collector in collectors.iter_mut() {
+        collector.collect(&system, &mut metrics);
     }
 
-    match system.memory() {
-        Ok(memory) => {
-            mem_free = memory.free.as_u64();
-            mem_total = memory.total.as_u64();
-            mem_usage = (mem_free as f64 / mem_total as f64) as f32;
+    if let Some(cgroup_counters_before) = cgroup_counters_before {
+        container = client_cgroup::get(&cgroup_counters_before, window);
+    }
+
+    if let Some(cpu_cores_load) = cpu_cores_load {
+        match cpu_cores_load.done() {
+            Ok(cpu_cores_load) => {
+                cpu_cores = get_cpu_cores(&cpu_cores_load);
+            }
+            Err(x) => warn!("CPU cores load: error: {}", x),
         }
-        Err(x) => println!("\nMemory: error: {}", x),
     }
 
-    match system.load_average() {
-        Ok(load_average) => {
-            sys_load_average_fifteen = load_average.fifteen;
-            sys_load_average_five = load_average.five;
-            sys_load_average_one = load_average.one;
+    if let Some(block_devices_before) = block_devices_before {
+        match system.block_device_statistics() {
+            Ok(block_devices_after) => {
+                disk_io = get_disk_io(&block_devices_before, &block_devices_after, window);
+            }
+            Err(x) => warn!("Block devices: error: {}", x),
+        }
+    }
+
+    if let Some(network_interfaces_before) = network_interfaces_before {
+        let network_interfaces_after: BTreeMap<String, NetworkStats> = get_network_stats(
+            &system,
+            network_interfaces_before.keys(),
+            client_config.network_interfaces(),
+        );
+        network_interfaces = get_network_interfaces(
+            &network_interfaces_before,
+            &network_interfaces_after,
+            window,
+        );
+    }
+
+    match system.mounts() {
+        Ok(mounts) => {
+            disk_mounts = get_disk_mounts(&mounts);
         }
-        Err(x) => println!("\nLoad average: error: {}", x),
+        Err(x) => warn!("Mounts: error: {}", x),
     }
 
-    match system.uptime() {
-        Ok(uptime) => {
-            sys_uptime = uptime.as_secs_f64();
+    if let Some(container) = &container {
+        if let Some(cpu_quota) = container.cpu_quota() {
+            let cpu_count: f64 = common_metric::get_f64(&metrics, "cpu.count")
+                .unwrap_or(0.0)
+                .min(cpu_quota.ceil().max(1.0));
+            client_collector::insert_integer(&mut metrics, "cpu.count", cpu_count as u64);
+        }
+
+        if let Some(memory_limit) = container.memory_limit() {
+            let mem_total: u64 = common_metric::get_f64(&metrics, "memory.total")
+                .map(|mem_total| mem_total as u64)
+                .unwrap_or(u64::MAX);
+
+            if *memory_limit < mem_total {
+                let memory_usage: u64 = container.memory_usage().unwrap_or(0);
+                let mem_free: u64 = memory_limit.saturating_sub(memory_usage);
+                client_collector::insert_integer(&mut metrics, "memory.available", mem_free);
+                client_collector::insert_integer(&mut metrics, "memory.free", mem_free);
+                client_collector::insert_integer(&mut metrics, "memory.total", *memory_limit);
+                client_collector::insert_float(
+                    &mut metrics,
+                    "memory.usage",
+                    (mem_free as f64 / *memory_limit as f64) as f32 as f64,
+                );
+                client_collector::insert_float(
+                    &mut metrics,
+                    "memory.used_percent",
+                    memory_usage as f64 / *memory_limit as f64 * 100.0,
+                );
+            }
         }
-        Err(x) => println!("\nuptime: error: {}", x),
     }
 
+    let metric = |name: &str| common_metric::get_f64(&metrics, name);
+    let cpu_count: Option<u16> = metric("cpu.count").map(|cpu_count| cpu_count as u16);
+    let cpu_idle: Option<f32> = metric("cpu.idle").map(|cpu_idle| cpu_idle as f32);
+    let cpu_interrupt: Option<f32> =
+        metric("cpu.interrupt").map(|cpu_interrupt| cpu_interrupt as f32);
+    let cpu_nice: Option<f32> = metric("cpu.nice").map(|cpu_nice| cpu_nice as f32);
+    let cpu_system: Option<f32> = metric("cpu.system").map(|cpu_system| cpu_system as f32);
+    let cpu_user: Option<f32> = metric("cpu.user").map(|cpu_user| cpu_user as f32);
+    let mem_available: Option<u64> =
+        metric("memory.available").map(|mem_available| mem_available as u64);
+    let mem_free: Option<u64> = metric("memory.free").map(|mem_free| mem_free as u64);
+    let mem_usage: Option<f32> = metric("memory.usage").map(|mem_usage| mem_usage as f32);
+    let mem_total: Option<u64> = metric("memory.total").map(|mem_total| mem_total as u64);
+    let mem_used_percent: Option<f32> =
+        metric("memory.used_percent").map(|mem_used_percent| mem_used_percent as f32);
+    let sys_load_average_fifteen: Option<f32> =
+        metric("load.fifteen").map(|load_average| load_average as f32);
+    let sys_load_average_five: Option<f32> =
+        metric("load.five").map(|load_average| load_average as f32);
+    let sys_load_average_one: Option<f32> =
+        metric("load.one").map(|load_average| load_average as f32);
+    let sys_uptime: Option<f64> = metric("uptime");
+    let collected_at: u64 = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
     ClientStatistics::new(
+        collected_at,
+        container,
+        containerized,
+        cpu_cores,
         cpu_count,
         cpu_idle,
         cpu_interrupt,
         cpu_nice,
         cpu_system,
         cpu_user,
+        disk_io,
+        disk_mounts,
+        mem_available,
         mem_free,
         mem_usage,
         mem_total,
+        mem_used_percent,
+        metrics,
+        network_interfaces,
         sys_load_average_fifteen,
         sys_load_average_five,
         sys_load_average_one,
         sys_uptime,
     )
 }
+
+fn get_cpu_cores(cpu_cores_load: &[CPULoad]) -> Vec<CpuCoreStatistics> {
+    let mut cpu_cores: Vec<CpuCoreStatistics> = vec![];
+    let cpu_info_frequencies: Vec<f32> = get_cpu_info_frequencies();
+
+    for (core, cpu) in cpu_cores_load.iter().enumerate() {
+        let frequency: Option<f32> =
+            get_cpu_frequency(core).or_else(|| cpu_info_frequencies.get(core).copied());
+
+        cpu_cores.push(CpuCoreStatistics::new(
+            core as u16,
+            frequency,
+            cpu.idle * 100.0,
+            get_cpu_iowait(cpu) * 100.0,
+            cpu.system * 100.0,
+            cpu.user * 100.0,
+        ));
+    }
+
+    cpu_cores
+}
+
+fn get_cpu_frequency(core: usize) -> Option<f32> {
+    let scaling_cur_freq_path: String = format!(
+        "/sys/devices/system/cpu/cpu{core}/cpufreq/scaling_cur_freq",
+        core = core
+    );
+    let scaling_cur_freq: String = fs::read_to_string(scaling_cur_freq_path).ok()?;
+    let frequency_khz: f32 = scaling_cur_freq.trim().parse().ok()?;
+
+    Some(frequency_khz / 1000.0)
+}
+
+fn get_cpu_info_frequencies() -> Vec<f32> {
+    let mut frequencies: Vec<f32> = vec![];
+
+    if let Ok(cpu_info) = fs::read_to_string("/proc/cpuinfo") {
+        for line in cpu_info.lines() {
+            if line.starts_with("cpu MHz") {
+                if let Some(frequency) = line.split(':').nth(1) {
+                    if let Ok(frequency) = frequency.trim().parse() {
+                        frequencies.push(frequency);
+                    }
+                }
+            }
+        }
+    }
+
+    frequencies
+}
+
+#[cfg(target_os = "linux")]
+fn get_cpu_iowait(cpu: &CPULoad) -> f32 {
+    cpu.platform.iowait
+}
+
+#[cfg(not(target_os = "linux"))]
+fn get_cpu_iowait(_cpu: &CPULoad) -> f32 {
+    0.0
+}
+
+fn get_disk_io(
+    before: &BTreeMap<String, BlockDeviceStats>,
+    after: &BTreeMap<String, BlockDeviceStats>,
+    window: u64,
+) -> Vec<DiskIoStatistics> {
+    let mut disk_io: Vec<DiskIoStatistics> = vec![];
+
+    for (name, after) in after {
+        if name.starts_with("loop") || name.starts_with("ram") {
+            continue;
+        }
+
+        if let Some(before) = before.get(name) {
+            let read_sectors: u64 = after.read_sectors.saturating_sub(before.read_sectors) as u64;
+            let write_sectors: u64 =
+                after.write_sectors.saturating_sub(before.write_sectors) as u64;
+
+            disk_io.push(DiskIoStatistics::new(
+                after.io_ticks.saturating_sub(before.io_ticks) as u64,
+                name.to_string(),
+                read_sectors * DISK_SECTOR_SIZE,
+                after.read_ios.saturating_sub(before.read_ios) as u64,
+                window,
+                write_sectors * DISK_SECTOR_SIZE,
+                after.write_ios.saturating_sub(before.write_ios) as u64,
+            ));
+        }
+    }
+
+    disk_io
+}
+
+fn get_disk_mounts(mounts: &[Filesystem]) -> Vec<DiskMountStatistics> {
+    let mut disk_mounts: Vec<DiskMountStatistics> = vec![];
+
+    for mount in mounts {
+        if mount.total.as_u64() == 0 || mount.fs_type == "tmpfs" || mount.fs_type == "devtmpfs" {
+            continue;
+        }
+
+        disk_mounts.push(DiskMountStatistics::new(
+            mount.avail.as_u64(),
+            mount.free.as_u64(),
+            mount.fs_type.to_string(),
+            mount.fs_mounted_from.to_string(),
+            mount.fs_mounted_on.to_string(),
+            mount.total.as_u64(),
+        ));
+    }
+
+    disk_mounts
+}
+
+fn get_network_interfaces(
+    before: &BTreeMap<String, NetworkStats>,
+    after: &BTreeMap<String, NetworkStats>,
+    window: u64,
+) -> Vec<NetworkInterfaceStatistics> {
+    let mut network_interfaces: Vec<NetworkInterfaceStatistics> = vec![];
+
+    for (name, after) in after {
+        if let Some(before) = before.get(name) {
+            network_interfaces.push(NetworkInterfaceStatistics::new(
+                name.to_string(),
+                after
+                    .rx_bytes
+                    .as_u64()
+                    .saturating_sub(before.rx_bytes.as_u64()),
+                after.rx_errors.saturating_sub(before.rx_errors),
+                after.rx_packets.saturating_sub(before.rx_packets),
+                after
+                    .tx_bytes
+                    .as_u64()
+                    .saturating_sub(before.tx_bytes.as_u64()),
+                after.tx_errors.saturating_sub(before.tx_errors),
+                after.tx_packets.saturating_sub(before.tx_packets),
+                window,
+            ));
+        }
+    }
+
+    network_interfaces
+}
+
+fn get_network_stats<'a>(
+    system: &System,
+    names: impl Iterator<Item = &'a String>,
+    allowlist: &Option<Vec<String>>,
+) -> BTreeMap<String, NetworkStats> {
+    let mut network_stats: BTreeMap<String, NetworkStats> = BTreeMap::new();
+
+    for name in names {
+        let allowed: bool = match allowlist {
+            Some(allowlist) => allowlist.contains(name),
+            None => name != "lo",
+        };
+
+        if !allowed {
+            continue;
+        }
+
+        match system.network_stats(name) {
+            Ok(stats) => {
+                network_stats.insert(name.to_string(), stats);
+            }
+            Err(x) => warn!("Network {}: error: {}", name, x),
+        }
+    }
+
+    network_stats
+}
@@ -0,0 +1,179 @@
+use crate::client_benchmark::ClientBenchmark;
+use crate::client_statistics::ClientStatistics;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use log::{info, warn};
+use serde::Serialize;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+#[derive(Debug, Default, Serialize)]
+pub struct ClientStatus {
+    last_benchmark: Option<ClientBenchmark>,
+    last_collected: Option<u64>,
+    last_error: Option<String>,
+    last_post: Option<u64>,
+    last_statistics: Option<ClientStatistics>,
+    posts_failed: u64,
+    posts_succeeded: u64,
+    queue_depth: usize,
+    server_public_key_fingerprint: Option<String>,
+}
+
+impl ClientStatus {
+    pub fn set_benchmark(&mut self, client_benchmark: &ClientBenchmark) {
+        self.last_benchmark = Some(client_benchmark.clone());
+    }
+
+    pub fn set_queue_depth(&mut self, queue_depth: usize) {
+        self.queue_depth = queue_depth;
+    }
+
+    pub fn set_server_public_key_fingerprint(&mut self, fingerprint: String) {
+        self.server_public_key_fingerprint = Some(fingerprint);
+    }
+
+    pub fn set_statistics(&mut self, client_statistics: &ClientStatistics) {
+        self.last_collected = Some(now());
+        self.last_statistics = Some(client_statistics.clone());
+    }
+
+    pub fn record_post_failure(&mut self, error: String) {
+        self.last_error = Some(error);
+        self.posts_failed += 1;
+    }
+
+    pub fn record_post_success(&mut self) {
+        self.last_post = Some(now());
+        self.posts_succeeded += 1;
+    }
+
+    fn render_metrics(&self) -> String {
+        let mut output: String = String::new();
+
+        write_metric(
+            &mut output,
+            "auditor_client_posts_total",
+            "counter",
+            "Statistics posts by result.",
+            &[
+                ("{result=\"failed\"}", self.posts_failed as f64),
+                ("{result=\"succeeded\"}", self.posts_succeeded as f64),
+            ],
+        );
+        write_metric(
+            &mut output,
+            "auditor_client_queue_depth",
+            "gauge",
+            "Statistics waiting to be posted.",
+            &[("", self.queue_depth as f64)],
+        );
+        write_metric(
+            &mut output,
+            "auditor_client_last_collected_timestamp_seconds",
+            "gauge",
+            "Time of the last statistics collection.",
+            &[("", self.last_collected.unwrap_or(0) as f64)],
+        );
+        write_metric(
+            &mut output,
+            "auditor_client_last_post_timestamp_seconds",
+            "gauge",
+            "Time of the last successful statistics post.",
+            &[("", self.last_post.unwrap_or(0) as f64)],
+        );
+
+        if let Some(last_benchmark) = &self.last_benchmark {
+            write_metric(
+                &mut output,
+                "auditor_client_benchmark_result",
+                "gauge",
+                "Results of the last benchmark run.",
+                &[
+                    ("{benchmark=\"mt_2\"}", *last_benchmark.mt_2_result()),
+                    ("{benchmark=\"mt_4\"}", *last_benchmark.mt_4_result()),
+                    ("{benchmark=\"mt_8\"}", *last_benchmark.mt_8_result()),
+                    ("{benchmark=\"st\"}", *last_benchmark.st_result()),
+                ],
+            );
+        }
+
+        output
+    }
+}
+
+pub async fn run(address: SocketAddr, client_status: Arc<Mutex<ClientStatus>>) {
+    let service = make_service_fn(move |_| {
+        let client_status: Arc<Mutex<ClientStatus>> = client_status.clone();
+
+        async {
+            Ok::<_, hyper::Error>(service_fn(move |req| handle(client_status.to_owned(), req)))
+        }
+    });
+
+    match Server::try_bind(&address) {
+        Ok(server) => {
+            info!("Status listening on {}", address);
+
+            if let Err(x) = server.serve(service).await {
+                warn!("Status server error = {}", x);
+            }
+        }
+        Err(x) => warn!("Can not bind status server to {}: {}", address, x),
+    }
+}
+
+async fn handle(
+    client_status: Arc<Mutex<ClientStatus>>,
+    request: Request<Body>,
+) -> Result<Response<Body>, hyper::Error> {
+    let client_status = client_status.lock().unwrap();
+    let (status, content_type, body): (StatusCode, &str, String) =
+        match (request.method(), request.uri().path()) {
+            (&Method::GET, "/metrics") => (
+                StatusCode::OK,
+                "text/plain; version=0.0.4",
+                client_status.render_metrics(),
+            ),
+            (&Method::GET, "/status") => (
+                StatusCode::OK,
+                "application/json",
+                serde_json::to_string(&*client_status).unwrap(),
+            ),
+            _ => (
+                StatusCode::NOT_FOUND,
+                "application/json",
+                String::from("{}"),
+            ),
+        };
+
+    Ok(Response::builder()
+        .status(status)
+        .header("content-type", content_type)
+        .body(Body::from(body))
+        .expect("response builder"))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn write_metric(
+    output: &mut String,
+    name: &str,
+    metric_type: &str,
+    help: &str,
+    values: &[(&str, f64)],
+) {
+    let _ = writeln!(output, "# HELP {} {}", name, help);
+    let _ = writeln!(output, "# TYPE {} {}", name, metric_type);
+
+    for (labels, value) in values {
+        let _ = writeln!(output, "{}{} {}", name, labels, value);
+    }
+}
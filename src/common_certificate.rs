@@ -0,0 +1,162 @@
+use crate::de;
+use crate::de::De;
+use clap::ArgMatches;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::time::SystemTime;
+
+pub const ALGORITHM: &str = "RSA-FDH-SHA256";
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Certificate {
+    algorithm: String,
+    body: String,
+    issued_at: u64,
+    public_key: String,
+    signature: String,
+}
+
+impl Certificate {
+    pub fn new(
+        algorithm: String,
+        body: String,
+        issued_at: u64,
+        public_key: String,
+        signature: String,
+    ) -> Self {
+        Self {
+            algorithm,
+            body,
+            issued_at,
+            public_key,
+            signature,
+        }
+    }
+
+    pub fn issued_at(&self) -> &u64 {
+        &self.issued_at
+    }
+
+    pub fn public_key(&self) -> &String {
+        &self.public_key
+    }
+}
+
+pub fn issue(de: &De, body: String) -> Certificate {
+    let issued_at: u64 = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let signature: String = de.sign(&signed_data(issued_at, &body));
+
+    Certificate::new(
+        ALGORITHM.to_string(),
+        body,
+        issued_at,
+        de.public_key_string(),
+        signature,
+    )
+}
+
+pub fn verify(certificate: &Certificate, trusted_fingerprint: &str) -> bool {
+    certificate.algorithm == ALGORITHM
+        && de::fingerprint(&certificate.public_key).eq_ignore_ascii_case(trusted_fingerprint.trim())
+        && de::check_sign(
+            &signed_data(certificate.issued_at, &certificate.body),
+            &certificate.public_key,
+            &certificate.signature,
+        )
+}
+
+pub fn run_verify(
+    verify_parameters: &ArgMatches,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let file: &str = verify_parameters.value_of("file").unwrap();
+    let trusted_fingerprint: String = match verify_parameters.value_of("public_key") {
+        Some(public_key_file) => {
+            let public_key_string: String = de::read_public_key_string(public_key_file)
+                .ok_or_else(|| format!("Can not read public key {}", public_key_file))?;
+            de::fingerprint(&public_key_string)
+        }
+        None => verify_parameters
+            .value_of("fingerprint")
+            .unwrap()
+            .to_string(),
+    };
+    let contents: String = fs::read_to_string(file)?;
+    let certificate: Certificate = serde_json::from_str(&contents)?;
+
+    if !de::fingerprint(certificate.public_key()).eq_ignore_ascii_case(trusted_fingerprint.trim()) {
+        return Err(format!(
+            "Certificate {} is not signed by the trusted key {}",
+            file, trusted_fingerprint
+        )
+        .into());
+    }
+
+    if !verify(&certificate, &trusted_fingerprint) {
+        return Err(format!("Certificate {} is not valid", file).into());
+    }
+
+    println!("Certificate {} is valid", file);
+    println!("issued_at = {}", certificate.issued_at());
+    println!(
+        "public_key_fingerprint = {}",
+        de::fingerprint(certificate.public_key())
+    );
+
+    Ok(())
+}
+
+fn signed_data(issued_at: u64, body: &str) -> String {
+    format!("{}.{}", issued_at, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+    use rsa::{RsaPrivateKey, RsaPublicKey};
+
+    fn get_de() -> De {
+        let private_key: RsaPrivateKey = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let public_key: RsaPublicKey = RsaPublicKey::from(&private_key);
+
+        De::new(None, private_key, public_key)
+    }
+
+    #[test]
+    fn verify_accepts_certificate_signed_by_trusted_key() {
+        let de: De = get_de();
+        let certificate: Certificate = issue(&de, String::from("{\"a\":1}"));
+
+        assert!(verify(
+            &certificate,
+            &de::fingerprint(&de.public_key_string())
+        ));
+    }
+
+    #[test]
+    fn verify_rejects_certificate_signed_by_other_key() {
+        let de: De = get_de();
+        let other_de: De = get_de();
+        let certificate: Certificate = issue(&other_de, String::from("{\"a\":1}"));
+
+        assert!(!verify(
+            &certificate,
+            &de::fingerprint(&de.public_key_string())
+        ));
+    }
+
+    #[test]
+    fn verify_rejects_modified_body() {
+        let de: De = get_de();
+        let mut certificate: Certificate = issue(&de, String::from("{\"a\":1}"));
+        certificate.body = String::from("{\"a\":2}");
+
+        assert!(!verify(
+            &certificate,
+            &de::fingerprint(&de.public_key_string())
+        ));
+    }
+}
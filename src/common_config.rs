@@ -0,0 +1,351 @@
+use clap::ArgMatches;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+use toml::value::Table;
+use toml::Value;
+
+pub const ENV_PREFIX: &str = "AUDITOR_";
+
+pub type ConfigSources = BTreeMap<String, String>;
+
+#[derive(Clone, Debug)]
+pub struct ConfigOverride {
+    key: String,
+    source: String,
+    value: String,
+}
+
+impl ConfigOverride {
+    pub fn new(key: String, source: String, value: String) -> Self {
+        Self { key, source, value }
+    }
+}
+
+pub fn get_cli_overrides(cli_parameters: &ArgMatches) -> Result<Vec<ConfigOverride>, String> {
+    let mut overrides: Vec<ConfigOverride> = vec![];
+
+    for (name, key) in [
+        ("log_file", "log.file"),
+        ("log_format", "log.format"),
+        ("log_level", "log.level"),
+    ] {
+        if let Some(value) = cli_parameters.value_of(name) {
+            overrides.push(ConfigOverride::new(
+                key.to_string(),
+                format!("cli --{}", name.replace('_', "-")),
+                quote(value),
+            ));
+        }
+    }
+
+    for setting in cli_parameters.values_of("set").into_iter().flatten() {
+        let (key, value): (&str, &str) = setting
+            .split_once('=')
+            .ok_or_else(|| format!("--set {}: expected KEY=VALUE", setting))?;
+
+        overrides.push(ConfigOverride::new(
+            key.trim().to_string(),
+            format!("cli --set {}", key.trim()),
+            value.to_string(),
+        ));
+    }
+
+    Ok(overrides)
+}
+
+pub fn get_env(name: &str) -> Option<(String, String)> {
+    let variable: String = format!("{}{}", ENV_PREFIX, name.to_uppercase());
+
+    env::var(&variable)
+        .ok()
+        .map(|value| (value, format!("env {}", variable)))
+}
+
+fn get_env_overrides() -> Vec<ConfigOverride> {
+    let mut overrides: Vec<ConfigOverride> = vec![];
+
+    for (variable, value) in env::vars() {
+        let name: &str = match variable.strip_prefix(ENV_PREFIX) {
+            Some(name) => name,
+            None => continue,
+        };
+        let key: String = match name {
+            "DATA_DIR" | "ETC_DIR" => continue,
+            "LOG" => String::from("log.level"),
+            _ => name.to_lowercase().replace("__", "."),
+        };
+        let value: String = if key == "log.level" {
+            quote(&value)
+        } else {
+            value
+        };
+
+        overrides.push(ConfigOverride::new(key, format!("env {}", variable), value));
+    }
+
+    overrides.sort_by(|a, b| a.key.cmp(&b.key));
+
+    overrides
+}
+
+pub fn check<T>(result: Result<T, String>, sources: &ConfigSources) -> Result<T, String> {
+    result.map_err(|message| {
+        let key: &str = message.split_once(':').map_or("", |x| x.0);
+
+        match get_source(sources, key) {
+            Some(source) => format!("{} (from {})", message, source),
+            None => message,
+        }
+    })
+}
+
+fn get_key(message: &str) -> Option<&str> {
+    let start: usize = message.find("for key `")? + "for key `".len();
+    let end: usize = message[start..].find('`')? + start;
+
+    Some(&message[start..end])
+}
+
+fn get_source<'a>(sources: &'a ConfigSources, key: &str) -> Option<&'a String> {
+    let mut key: &str = key;
+
+    loop {
+        if let Some(source) = sources.get(key) {
+            return Some(source);
+        }
+
+        key = &key[..key.rfind('.')?];
+    }
+}
+
+fn insert(table: &mut Table, key: &str, value: Value) -> Result<(), String> {
+    let mut table: &mut Table = table;
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last: &str = parts
+        .pop()
+        .filter(|last| !last.is_empty())
+        .ok_or_else(|| format!("key `{}`: is empty", key))?;
+
+    for part in parts {
+        table = match table
+            .entry(part.to_string())
+            .or_insert_with(|| Value::Table(Table::new()))
+        {
+            Value::Table(table) => table,
+            _ => return Err(format!("key `{}`: {} is not a table", key, part)),
+        };
+    }
+
+    table.insert(last.to_string(), value);
+
+    Ok(())
+}
+
+fn insert_sources(sources: &mut ConfigSources, prefix: &str, table: &Table, source: &str) {
+    for (key, value) in table {
+        let key: String = if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+
+        match value {
+            Value::Table(table) => insert_sources(sources, &key, table, source),
+            _ => {
+                sources.insert(key, source.to_string());
+            }
+        }
+    }
+}
+
+pub fn load<T: DeserializeOwned>(
+    config_file_name: &str,
+    cli_overrides: &[ConfigOverride],
+) -> Result<(T, ConfigSources), String> {
+    let mut sources: ConfigSources = ConfigSources::new();
+    let mut table: Table = if Path::new(config_file_name).exists() {
+        let config_file_contents: String = fs::read_to_string(config_file_name).map_err(|x| {
+            format!(
+                "Can not open configuration file {}: {}",
+                config_file_name, x
+            )
+        })?;
+
+        toml::from_str(&config_file_contents)
+            .map_err(|x| format!("Invalid configuration file {}: {}", config_file_name, x))?
+    } else {
+        Table::new()
+    };
+
+    insert_sources(
+        &mut sources,
+        "",
+        &table,
+        &format!("file {}", config_file_name),
+    );
+
+    for config_override in get_env_overrides().iter().chain(cli_overrides) {
+        let value: Value = parse_value(&config_override.value);
+        let prefix: String = format!("{}.", config_override.key);
+
+        insert(&mut table, &config_override.key, value)
+            .map_err(|x| format!("{} (from {})", x, config_override.source))?;
+        sources.retain(|key, _| !key.starts_with(&prefix));
+        sources.insert(
+            config_override.key.to_string(),
+            config_override.source.to_string(),
+        );
+    }
+
+    let merged: String = toml::to_string(&Value::Table(table)).map_err(|x| x.to_string())?;
+    let config: T = toml::from_str(&merged).map_err(|x| {
+        let message: String = x.to_string();
+        let message: &str = match x.line_col() {
+            Some(_) => message
+                .rsplit_once(" at line ")
+                .map_or(message.as_str(), |x| x.0),
+            None => message.as_str(),
+        };
+
+        match get_key(message).and_then(|key| get_source(&sources, key)) {
+            Some(source) => format!("Invalid configuration: {} (from {})", message, source),
+            None => format!("Invalid configuration: {}", message),
+        }
+    })?;
+
+    Ok((config, sources))
+}
+
+fn parse_value(value: &str) -> Value {
+    toml::from_str::<Table>(&format!("value = {}", value))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(value.to_string()))
+}
+
+pub fn print<T: Serialize>(config: &T, sources: &ConfigSources) -> Result<(), String> {
+    let value: Value = Value::try_from(config).map_err(|x| x.to_string())?;
+    let mut lines: Vec<(String, String)> = vec![];
+
+    if let Value::Table(table) = value {
+        push_lines(&mut lines, "", &table);
+    }
+
+    for (key, value) in lines {
+        let source: &str = get_source(sources, &key).map_or("default", |source| source.as_str());
+
+        println!("{} = {} # {}", key, value, source);
+    }
+
+    Ok(())
+}
+
+fn push_lines(lines: &mut Vec<(String, String)>, prefix: &str, table: &Table) {
+    for (key, value) in table {
+        let key: String = if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+
+        match value {
+            Value::Table(table) if !table.is_empty() => push_lines(lines, &key, table),
+            _ => lines.push((key, value.to_string())),
+        }
+    }
+}
+
+fn quote(value: &str) -> String {
+    Value::String(value.to_string()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct TestConfig {
+        test_precedence: TestPrecedenceConfig,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestPrecedenceConfig {
+        cli: u64,
+        env: u64,
+        file: u64,
+    }
+
+    fn get_config_file_name(name: &str, contents: &str) -> String {
+        let config_file_name: String = format!(
+            "{}/auditor_config_{}_{}.toml",
+            env::temp_dir().display(),
+            std::process::id(),
+            name
+        );
+        fs::write(&config_file_name, contents).unwrap();
+
+        config_file_name
+    }
+
+    #[test]
+    fn load_applies_file_then_env_then_cli() {
+        let config_file_name: String = get_config_file_name(
+            "precedence",
+            "[test_precedence]\ncli = 1\nenv = 1\nfile = 1\n",
+        );
+        env::set_var("AUDITOR_TEST_PRECEDENCE__CLI", "2");
+        env::set_var("AUDITOR_TEST_PRECEDENCE__ENV", "2");
+        let cli_overrides: Vec<ConfigOverride> = vec![ConfigOverride::new(
+            String::from("test_precedence.cli"),
+            String::from("cli --set test_precedence.cli"),
+            String::from("3"),
+        )];
+
+        let result: Result<(TestConfig, ConfigSources), String> =
+            load(&config_file_name, &cli_overrides);
+        env::remove_var("AUDITOR_TEST_PRECEDENCE__CLI");
+        env::remove_var("AUDITOR_TEST_PRECEDENCE__ENV");
+        fs::remove_file(&config_file_name).unwrap();
+        let (config, sources): (TestConfig, ConfigSources) = result.unwrap();
+
+        assert_eq!(config.test_precedence.file, 1);
+        assert_eq!(config.test_precedence.env, 2);
+        assert_eq!(config.test_precedence.cli, 3);
+        assert_eq!(
+            sources["test_precedence.file"],
+            format!("file {}", config_file_name)
+        );
+        assert_eq!(
+            sources["test_precedence.env"],
+            "env AUDITOR_TEST_PRECEDENCE__ENV"
+        );
+        assert_eq!(
+            sources["test_precedence.cli"],
+            "cli --set test_precedence.cli"
+        );
+    }
+
+    #[test]
+    fn load_names_the_source_of_an_invalid_value() {
+        let config_file_name: String =
+            get_config_file_name("invalid", "[test_precedence]\ncli = 1\nenv = 1\nfile = 1\n");
+        let cli_overrides: Vec<ConfigOverride> = vec![ConfigOverride::new(
+            String::from("test_precedence.cli"),
+            String::from("cli --set test_precedence.cli"),
+            String::from("\"three\""),
+        )];
+
+        let result: Result<(TestConfig, ConfigSources), String> =
+            load(&config_file_name, &cli_overrides);
+        fs::remove_file(&config_file_name).unwrap();
+
+        assert!(result
+            .unwrap_err()
+            .ends_with("(from cli --set test_precedence.cli)"));
+    }
+}
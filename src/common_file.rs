@@ -0,0 +1,43 @@
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+pub fn write_atomic(path: &str, contents: &[u8]) -> std::io::Result<()> {
+    let temporary_path: String = format!("{}.tmp", path);
+    let mut temporary_file: File = File::create(&temporary_path)?;
+
+    temporary_file.write_all(contents)?;
+    temporary_file.sync_all()?;
+    fs::rename(&temporary_path, path)?;
+
+    let directory: &Path = match Path::new(path).parent() {
+        Some(directory) if !directory.as_os_str().is_empty() => directory,
+        _ => Path::new("."),
+    };
+
+    File::open(directory)?.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_atomic_replaces_file_without_leaving_temporary_file() {
+        let path: String = format!(
+            "{}/auditor_write_atomic_{}.json",
+            std::env::temp_dir().display(),
+            std::process::id()
+        );
+
+        write_atomic(&path, b"[1]").unwrap();
+        write_atomic(&path, b"[2]").unwrap();
+        let contents: String = fs::read_to_string(&path).unwrap();
+        let temporary_exists: bool = Path::new(&format!("{}.tmp", path)).exists();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(contents, "[2]");
+        assert!(!temporary_exists);
+    }
+}
@@ -1,30 +1,291 @@
+use crate::config::Config;
 use chrono::{DateTime, Utc};
 use log::{Level, Metadata, Record};
 use log::{LevelFilter, SetLoggerError};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::Mutex;
 
-static LOGGER: SimpleLogger = SimpleLogger;
-struct SimpleLogger;
+static LOGGER: SimpleLogger = SimpleLogger {
+    state: Mutex::new(None),
+};
+
+thread_local! {
+    static FIELDS: RefCell<Vec<(&'static str, Value)>> = const { RefCell::new(vec![]) };
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Json,
+    Text,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LogConfig {
+    #[serde(default)]
+    file: Option<String>,
+    #[serde(default = "default_format")]
+    format: LogFormat,
+    #[serde(default = "default_level")]
+    level: String,
+    #[serde(default = "default_max_files")]
+    max_files: usize,
+    #[serde(default = "default_max_size")]
+    max_size: u64,
+}
+
+impl LogConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        parse_levels(&self.level).map(|_| ())
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            file: None,
+            format: default_format(),
+            level: default_level(),
+            max_files: default_max_files(),
+            max_size: default_max_size(),
+        }
+    }
+}
+
+struct LogFile {
+    file: File,
+    max_files: usize,
+    max_size: u64,
+    path: String,
+    size: u64,
+}
+
+impl LogFile {
+    fn open(path: &str, max_files: usize, max_size: u64) -> Result<Self, String> {
+        let file: File = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .map_err(|x| format!("log file = {}: {}", path, x))?;
+        let size: u64 = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+
+        Ok(Self {
+            file,
+            max_files,
+            max_size,
+            path: path.to_string(),
+            size,
+        })
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        if self.max_files == 0 {
+            self.file.set_len(0)?;
+        } else {
+            for index in (1..self.max_files).rev() {
+                let from: String = format!("{}.{}", self.path, index);
+
+                if fs::metadata(&from).is_ok() {
+                    fs::rename(&from, format!("{}.{}", self.path, index + 1))?;
+                }
+            }
+
+            fs::rename(&self.path, format!("{}.1", self.path))?;
+            self.file = OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(&self.path)?;
+        }
+
+        self.size = 0;
+
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        if self.max_size > 0 && self.size > 0 && self.size + line.len() as u64 + 1 > self.max_size {
+            self.rotate()?;
+        }
+
+        writeln!(self.file, "{}", line)?;
+        self.size += line.len() as u64 + 1;
+
+        Ok(())
+    }
+}
+
+struct LoggerState {
+    default_level: LevelFilter,
+    directives: Vec<(String, LevelFilter)>,
+    file: Option<LogFile>,
+    format: LogFormat,
+    host_id: String,
+}
+
+impl LoggerState {
+    fn level(&self, target: &str) -> LevelFilter {
+        self.directives
+            .iter()
+            .filter(|(module, _)| {
+                target == module
+                    || target
+                        .strip_prefix(module.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+            .max_by_key(|(module, _)| module.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default_level)
+    }
+}
+
+struct SimpleLogger {
+    state: Mutex<Option<LoggerState>>,
+}
 
 impl log::Log for SimpleLogger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= Level::Info
+        match &*self.state.lock().unwrap() {
+            Some(state) => metadata.level() <= state.level(metadata.target()),
+            None => metadata.level() <= Level::Info,
+        }
     }
 
     fn log(&self, record: &Record) {
-        if self.enabled(record.metadata()) {
-            let now: DateTime<Utc> = Utc::now();
-            println!(
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let now: DateTime<Utc> = Utc::now();
+        let mut state = self.state.lock().unwrap();
+        let line: String = match &*state {
+            Some(state) if state.format == LogFormat::Json => {
+                let mut line: Map<String, Value> = Map::new();
+                line.insert("timestamp".to_string(), Value::from(now.to_rfc3339()));
+                line.insert("level".to_string(), Value::from(record.level().as_str()));
+                line.insert("target".to_string(), Value::from(record.target()));
+                line.insert("host_id".to_string(), Value::from(state.host_id.as_str()));
+                line.insert(
+                    "message".to_string(),
+                    Value::from(record.args().to_string()),
+                );
+                FIELDS.with(|fields| {
+                    for (name, value) in fields.borrow().iter() {
+                        line.insert(name.to_string(), value.clone());
+                    }
+                });
+
+                Value::Object(line).to_string()
+            }
+            _ => format!(
                 "{} - {} - {}",
                 now.to_rfc3339(),
                 record.level(),
                 record.args()
-            );
+            ),
+        };
+
+        match state.as_mut().and_then(|state| state.file.as_mut()) {
+            Some(file) => {
+                if let Err(x) = file.write_line(&line) {
+                    eprintln!("Can not write log file: {}", x);
+                    eprintln!("{}", line);
+                }
+            }
+            None => println!("{}", line),
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = self
+            .state
+            .lock()
+            .unwrap()
+            .as_mut()
+            .and_then(|state| state.file.as_mut())
+        {
+            let _ = file.file.flush();
+        }
+    }
+}
+
+fn default_format() -> LogFormat {
+    LogFormat::Text
+}
+
+fn default_level() -> String {
+    "info".to_string()
+}
+
+fn default_max_files() -> usize {
+    5
+}
+
+fn default_max_size() -> u64 {
+    10485760
+}
+
+fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    LevelFilter::from_str(level).map_err(|_| format!("log.level: {} is not a valid level", level))
+}
+
+fn parse_levels(levels: &str) -> Result<(LevelFilter, Vec<(String, LevelFilter)>), String> {
+    let mut default_level: LevelFilter = LevelFilter::Info;
+    let mut directives: Vec<(String, LevelFilter)> = vec![];
+
+    for directive in levels.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+        match directive.split_once('=') {
+            Some((module, level)) => {
+                directives.push((module.trim().to_string(), parse_level(level.trim())?))
+            }
+            None => default_level = parse_level(directive)?,
         }
     }
 
-    fn flush(&self) {}
+    Ok((default_level, directives))
+}
+
+pub fn configure(config: &Config, log_config: &LogConfig) -> Result<(), String> {
+    let (default_level, directives): (LevelFilter, Vec<(String, LevelFilter)>) =
+        parse_levels(&log_config.level)?;
+    let file: Option<LogFile> = match &log_config.file {
+        Some(path) => Some(LogFile::open(
+            path,
+            log_config.max_files,
+            log_config.max_size,
+        )?),
+        None => None,
+    };
+    let max_level: LevelFilter = directives
+        .iter()
+        .map(|(_, level)| *level)
+        .fold(default_level, Ord::max);
+
+    *LOGGER.state.lock().unwrap() = Some(LoggerState {
+        default_level,
+        directives,
+        file,
+        format: log_config.format,
+        host_id: config.host_id().to_string(),
+    });
+    log::set_max_level(max_level);
+
+    Ok(())
 }
 
 pub fn init() -> Result<(), SetLoggerError> {
     log::set_logger(&LOGGER).map(|()| log::set_max_level(LevelFilter::Info))
 }
+
+pub fn with_fields<T>(fields: Vec<(&'static str, Value)>, log: impl FnOnce() -> T) -> T {
+    FIELDS.with(|current| *current.borrow_mut() = fields);
+    let result: T = log();
+    FIELDS.with(|current| current.borrow_mut().clear());
+
+    result
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub type Metrics = BTreeMap<String, MetricValue>;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum MetricValue {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl MetricValue {
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            MetricValue::Bool(value) => Some(if *value { 1.0 } else { 0.0 }),
+            MetricValue::Integer(value) => Some(*value as f64),
+            MetricValue::Float(value) => Some(*value),
+            MetricValue::Text(_) => None,
+        }
+    }
+}
+
+pub fn get_f64(metrics: &Metrics, name: &str) -> Option<f64> {
+    metrics.get(name).and_then(|value| value.as_f64())
+}
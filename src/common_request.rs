@@ -1,15 +1,39 @@
 use hyper::{Body, Request, Response};
+use std::collections::HashMap;
+
+pub fn get_query_parameters(request: &Request<Body>) -> HashMap<String, String> {
+    let mut query_parameters: HashMap<String, String> = HashMap::new();
+
+    if let Some(query) = request.uri().query() {
+        for pair in query.split('&') {
+            let mut pair = pair.splitn(2, '=');
+            let key: String = percent_decode(pair.next().unwrap_or(""));
+            let value: String = percent_decode(pair.next().unwrap_or(""));
+
+            if !key.is_empty() {
+                query_parameters.insert(key, value);
+            }
+        }
+    }
+
+    query_parameters
+}
+
+pub fn get_previous_signature_from_request(request: &Request<Body>) -> Option<String> {
+    request
+        .headers()
+        .get("previous-signature")
+        .and_then(|previous_signature| previous_signature.to_str().ok())
+        .map(|previous_signature| previous_signature.to_string())
+}
 
 pub fn get_signature_from_request(request: &Request<Body>) -> String {
-    let signature: String = String::from_utf8(
-        request
-            .headers()
-            .get("signature")
-            .unwrap()
-            .as_bytes()
-            .to_vec(),
-    )
-    .unwrap();
+    let signature: String = request
+        .headers()
+        .get("signature")
+        .and_then(|signature| signature.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
 
     signature
 }
@@ -27,3 +51,30 @@ pub fn get_signature_from_response(response: &Response<Body>) -> String {
 
     signature
 }
+
+fn percent_decode(input: &str) -> String {
+    let input: &[u8] = input.as_bytes();
+    let mut decoded: Vec<u8> = vec![];
+    let mut index: usize = 0;
+
+    while index < input.len() {
+        match input[index] {
+            b'+' => decoded.push(b' '),
+            b'%' if index + 2 < input.len() => {
+                match u8::from_str_radix(&String::from_utf8_lossy(&input[index + 1..index + 3]), 16)
+                {
+                    Ok(byte) => {
+                        decoded.push(byte);
+                        index += 2;
+                    }
+                    Err(_) => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+
+        index += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ContainerStatistics {
+    cgroup_version: u8,
+    cpu_periods: u64,
+    cpu_quota: Option<f64>,
+    cpu_throttled_periods: u64,
+    cpu_throttled_time: u64,
+    cpu_usage: Option<f32>,
+    memory_limit: Option<u64>,
+    memory_usage: Option<u64>,
+}
+
+impl ContainerStatistics {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        cgroup_version: u8,
+        cpu_periods: u64,
+        cpu_quota: Option<f64>,
+        cpu_throttled_periods: u64,
+        cpu_throttled_time: u64,
+        cpu_usage: Option<f32>,
+        memory_limit: Option<u64>,
+        memory_usage: Option<u64>,
+    ) -> Self {
+        Self {
+            cgroup_version,
+            cpu_periods,
+            cpu_quota,
+            cpu_throttled_periods,
+            cpu_throttled_time,
+            cpu_usage,
+            memory_limit,
+            memory_usage,
+        }
+    }
+
+    pub fn cpu_quota(&self) -> &Option<f64> {
+        &self.cpu_quota
+    }
+
+    pub fn cpu_usage(&self) -> &Option<f32> {
+        &self.cpu_usage
+    }
+
+    pub fn memory_limit(&self) -> &Option<u64> {
+        &self.memory_limit
+    }
+
+    pub fn memory_usage(&self) -> &Option<u64> {
+        &self.memory_usage
+    }
+}
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CpuCoreStatistics {
+    core: u16,
+    frequency: Option<f32>,
+    idle: f32,
+    iowait: f32,
+    system: f32,
+    user: f32,
+}
+
+impl CpuCoreStatistics {
+    pub fn new(
+        core: u16,
+        frequency: Option<f32>,
+        idle: f32,
+        iowait: f32,
+        system: f32,
+        user: f32,
+    ) -> Self {
+        Self {
+            core,
+            frequency,
+            idle,
+            iowait,
+            system,
+            user,
+        }
+    }
+
+    pub fn busy(&self) -> f32 {
+        (100.0 - self.idle).max(0.0)
+    }
+
+    pub fn core(&self) -> &u16 {
+        &self.core
+    }
+
+    pub fn frequency(&self) -> &Option<f32> {
+        &self.frequency
+    }
+
+    pub fn idle(&self) -> &f32 {
+        &self.idle
+    }
+
+    pub fn iowait(&self) -> &f32 {
+        &self.iowait
+    }
+
+    pub fn system(&self) -> &f32 {
+        &self.system
+    }
+
+    pub fn user(&self) -> &f32 {
+        &self.user
+    }
+}
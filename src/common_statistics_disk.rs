@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DiskIoStatistics {
+    busy_time: u64,
+    name: String,
+    read_bytes: u64,
+    reads: u64,
+    window: u64,
+    write_bytes: u64,
+    writes: u64,
+}
+
+impl DiskIoStatistics {
+    pub fn new(
+        busy_time: u64,
+        name: String,
+        read_bytes: u64,
+        reads: u64,
+        window: u64,
+        write_bytes: u64,
+        writes: u64,
+    ) -> Self {
+        Self {
+            busy_time,
+            name,
+            read_bytes,
+            reads,
+            window,
+            write_bytes,
+            writes,
+        }
+    }
+
+    pub fn busy_ratio(&self) -> f64 {
+        if self.window == 0 {
+            return 0.0;
+        }
+
+        (self.busy_time as f64 / self.window as f64).min(1.0)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DiskMountStatistics {
+    avail: u64,
+    free: u64,
+    fs_type: String,
+    mounted_from: String,
+    mounted_on: String,
+    total: u64,
+}
+
+impl DiskMountStatistics {
+    pub fn new(
+        avail: u64,
+        free: u64,
+        fs_type: String,
+        mounted_from: String,
+        mounted_on: String,
+        total: u64,
+    ) -> Self {
+        Self {
+            avail,
+            free,
+            fs_type,
+            mounted_from,
+            mounted_on,
+            total,
+        }
+    }
+}
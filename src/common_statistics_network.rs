@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NetworkInterfaceStatistics {
+    name: String,
+    rx_bytes: u64,
+    rx_errors: u64,
+    rx_packets: u64,
+    tx_bytes: u64,
+    tx_errors: u64,
+    tx_packets: u64,
+    window: u64,
+}
+
+impl NetworkInterfaceStatistics {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        name: String,
+        rx_bytes: u64,
+        rx_errors: u64,
+        rx_packets: u64,
+        tx_bytes: u64,
+        tx_errors: u64,
+        tx_packets: u64,
+        window: u64,
+    ) -> Self {
+        Self {
+            name,
+            rx_bytes,
+            rx_errors,
+            rx_packets,
+            tx_bytes,
+            tx_errors,
+            tx_packets,
+            window,
+        }
+    }
+
+    pub fn bytes_per_second(&self) -> f64 {
+        if self.window == 0 {
+            return 0.0;
+        }
+
+        (self.rx_bytes + self.tx_bytes) as f64 * 1000.0 / self.window as f64
+    }
+}
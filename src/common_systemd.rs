@@ -0,0 +1,78 @@
+use log::warn;
+use std::env;
+use std::net::TcpListener;
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::io::{FromRawFd, RawFd};
+use std::os::unix::net::{SocketAddr, UnixDatagram};
+use std::time::Duration;
+
+const LISTEN_FDS_START: RawFd = 3;
+
+pub fn is_enabled() -> bool {
+    env::var_os("NOTIFY_SOCKET").is_some()
+}
+
+pub fn listeners() -> Vec<(String, TcpListener)> {
+    let listen_pid: Option<u32> = env::var("LISTEN_PID")
+        .ok()
+        .and_then(|listen_pid| listen_pid.parse().ok());
+    let listen_fds: Option<RawFd> = env::var("LISTEN_FDS")
+        .ok()
+        .and_then(|listen_fds| listen_fds.parse().ok());
+    let listen_fdnames: Vec<String> = env::var("LISTEN_FDNAMES")
+        .map(|listen_fdnames| listen_fdnames.split(':').map(String::from).collect())
+        .unwrap_or_default();
+
+    env::remove_var("LISTEN_FDNAMES");
+    env::remove_var("LISTEN_FDS");
+    env::remove_var("LISTEN_PID");
+
+    match (listen_pid, listen_fds) {
+        (Some(listen_pid), Some(listen_fds)) if listen_pid == std::process::id() => (0..listen_fds)
+            .map(|index| {
+                (
+                    listen_fdnames
+                        .get(index as usize)
+                        .cloned()
+                        .unwrap_or_default(),
+                    unsafe { TcpListener::from_raw_fd(LISTEN_FDS_START + index) },
+                )
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+pub fn notify(state: &str) {
+    let notify_socket: String = match env::var("NOTIFY_SOCKET") {
+        Ok(notify_socket) => notify_socket,
+        Err(_) => return,
+    };
+    let result: std::io::Result<usize> =
+        UnixDatagram::unbound().and_then(|socket| match notify_socket.strip_prefix('@') {
+            Some(name) => {
+                socket.send_to_addr(state.as_bytes(), &SocketAddr::from_abstract_name(name)?)
+            }
+            None => socket.send_to(state.as_bytes(), &notify_socket),
+        });
+
+    if let Err(x) = result {
+        warn!("Can not notify systemd: {}", x);
+    }
+}
+
+pub fn watchdog_interval() -> Option<Duration> {
+    let watchdog_pid: Option<u32> = env::var("WATCHDOG_PID")
+        .ok()
+        .and_then(|watchdog_pid| watchdog_pid.parse().ok());
+
+    if watchdog_pid.is_some_and(|watchdog_pid| watchdog_pid != std::process::id()) {
+        return None;
+    }
+
+    env::var("WATCHDOG_USEC")
+        .ok()
+        .and_then(|watchdog_usec| watchdog_usec.parse().ok())
+        .filter(|watchdog_usec| *watchdog_usec > 0)
+        .map(|watchdog_usec: u64| Duration::from_micros(watchdog_usec / 2))
+}
@@ -1,3 +1,5 @@
+use crate::common_config;
+use crate::common_config::{ConfigOverride, ConfigSources};
 use crate::info::Info;
 use crate::mode::Mode;
 use clap::ArgMatches;
@@ -13,15 +15,26 @@ pub struct Config {
     etc_dir: String,
     host_id: String,
     mode: Mode,
+    overrides: Vec<ConfigOverride>,
+    sources: ConfigSources,
 }
 
 impl Config {
-    pub fn new(data_dir: String, etc_dir: String, host_id: String, mode: Mode) -> Self {
+    pub fn new(
+        data_dir: String,
+        etc_dir: String,
+        host_id: String,
+        mode: Mode,
+        overrides: Vec<ConfigOverride>,
+        sources: ConfigSources,
+    ) -> Self {
         Self {
             data_dir,
             etc_dir,
             host_id,
             mode,
+            overrides,
+            sources,
         }
     }
 
@@ -41,40 +54,71 @@ impl Config {
         &self.mode
     }
 
+    pub fn overrides(&self) -> &Vec<ConfigOverride> {
+        &self.overrides
+    }
+
     pub fn print(&self) {
+        let source = |key: &str| -> &str {
+            self.sources
+                .get(key)
+                .map_or("default", |source| source.as_str())
+        };
         let mut info: String = String::new();
-        info.push_str(format!("data_dir = {} \n", self.data_dir()).as_str());
-        info.push_str(format!("etc_dir = {} \n", self.etc_dir()).as_str());
-        info.push_str(format!("host_id = {} \n", self.host_id()).as_str());
-        info.push_str(format!("mode = {:?} \n", self.mode()).as_str());
+        info.push_str(
+            format!(
+                "data_dir = {:?} # {}\n",
+                self.data_dir(),
+                source("data_dir")
+            )
+            .as_str(),
+        );
+        info.push_str(format!("etc_dir = {:?} # {}\n", self.etc_dir(), source("etc_dir")).as_str());
+        info.push_str(format!("host_id = {:?} # {}\n", self.host_id(), source("host_id")).as_str());
+        info.push_str(format!("mode = {:?} # {}", self.mode(), source("mode")).as_str());
         println!("{}", info);
     }
 }
 
-pub fn parse(cli_parameters: &ArgMatches, info: &Info) -> Config {
+pub fn parse(cli_parameters: &ArgMatches, info: &Info) -> Result<Config, String> {
     let mode: Mode;
+    let mut sources: ConfigSources = ConfigSources::new();
+    let mut get_dir = |name: &str, default_dir: String| -> Result<String, String> {
+        let (dir, source): (String, String) = match cli_parameters.value_of(name) {
+            Some(dir) => (dir.to_string(), format!("cli --{}", name.replace('_', "-"))),
+            None => match common_config::get_env(name) {
+                Some((dir, source)) => (dir, source),
+                None => (default_dir, String::from("default")),
+            },
+        };
+
+        if !Path::new(dir.as_str()).exists() {
+            return Err(format!(
+                "{}: directory {} does not exist (from {})",
+                name, dir, source
+            ));
+        }
 
-    let default_data_dir: String = format!("/usr/share/{bn}/", bn = info.bin_name());
-    let data_dir: String = cli_parameters
-        .value_of("data_dir")
-        .unwrap_or(&default_data_dir)
-        .to_string();
-
-    if !Path::new(data_dir.as_str()).exists() {
-        panic!("Data directory doesn't exists.");
-    }
-
-    let default_etc_dir: String = format!("/etc/{bn}/", bn = info.bin_name());
-    let etc_dir: String = cli_parameters
-        .value_of("etc_dir")
-        .unwrap_or(&default_etc_dir)
-        .to_string();
-
-    if !Path::new(etc_dir.as_str()).exists() {
-        panic!("Etc directory doesn't exists.");
-    }
-
-    if 1 == cli_parameters.occurrences_of("server_mode") {
+        sources.insert(name.to_string(), source);
+
+        Ok(dir)
+    };
+    let data_dir: String = get_dir(
+        "data_dir",
+        format!("/usr/share/{bn}/", bn = info.bin_name()),
+    )?;
+    let etc_dir: String = get_dir("etc_dir", format!("/etc/{bn}/", bn = info.bin_name()))?;
+
+    if cli_parameters.subcommand_matches("audit").is_some() {
+        mode = Mode::Audit
+    } else if cli_parameters
+        .subcommand_matches("integrity-check")
+        .is_some()
+    {
+        mode = Mode::IntegrityCheck
+    } else if cli_parameters.subcommand_matches("report").is_some() {
+        mode = Mode::Report
+    } else if 1 == cli_parameters.occurrences_of("server_mode") {
         mode = Mode::Server
     } else if 1 == cli_parameters.occurrences_of("client_load_simulator_mode") {
         mode = Mode::ClientLoadSimulator
@@ -84,20 +128,33 @@ pub fn parse(cli_parameters: &ArgMatches, info: &Info) -> Config {
 
     let host_id_path: String = format!("{dir}{file}", dir = data_dir, file = "host_id.dat");
     let host_id: String = get_host_id(&host_id_path);
-
-    Config::new(data_dir, etc_dir, host_id, mode)
+    let overrides: Vec<ConfigOverride> = common_config::get_cli_overrides(cli_parameters)?;
+
+    sources.insert(String::from("host_id"), format!("file {}", host_id_path));
+    sources.insert(
+        String::from("mode"),
+        if matches!(mode, Mode::Client) && 0 == cli_parameters.occurrences_of("client_mode") {
+            String::from("default")
+        } else {
+            String::from("cli")
+        },
+    );
+
+    Ok(Config::new(
+        data_dir, etc_dir, host_id, mode, overrides, sources,
+    ))
 }
 
 fn get_host_id(host_id_path: &str) -> String {
     if !Path::new(host_id_path).exists() {
         let host_id: String = Uuid::new_v4().to_string();
-        let mut host_id_file: File = File::create(&host_id_path).expect("Failed to create a file");
+        let mut host_id_file: File = File::create(host_id_path).expect("Failed to create a file");
         host_id_file
             .write_all(host_id.as_bytes())
             .expect("Failed to write host id");
         host_id
     } else {
-        let mut host_id_file: File = File::open(&host_id_path).expect("Failed to open a file");
+        let mut host_id_file: File = File::open(host_id_path).expect("Failed to open a file");
         let mut host_id: String = String::new();
         host_id_file
             .read_to_string(&mut host_id)
@@ -1,28 +1,68 @@
+use crate::common_file;
+use crate::db_chain::DbChain;
 use crate::db_record::DbRecord;
+use crate::de::De;
 use lz4_flex::{compress_prepend_size, decompress_size_prepended};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Deserialize, Serialize)]
+struct DbFile<D> {
+    chain: DbChain,
+    database: D,
+}
+
+#[derive(Debug)]
 pub struct Db {
+    chain: DbChain,
     database: HashMap<String, DbRecord>,
     data_dir: String,
+    de: De,
+    save_count: u64,
+    save_duration_total: Duration,
+    save_size: usize,
 }
 
 impl Db {
-    pub fn new(data_dir: String) -> Self {
+    pub fn new(data_dir: String, de: De) -> Self {
         Self {
+            chain: DbChain::default(),
             database: HashMap::new(),
             data_dir,
+            de,
+            save_count: 0,
+            save_duration_total: Duration::ZERO,
+            save_size: 0,
         }
     }
 
-    pub fn get(&mut self, key: &str) -> Option<&DbRecord> {
+    pub fn chain(&self) -> &DbChain {
+        &self.chain
+    }
+
+    pub fn set_chain(&mut self, chain: DbChain) {
+        self.chain = chain;
+    }
+
+    pub fn get(&self, key: &str) -> Option<&DbRecord> {
         self.database.get(key)
     }
 
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut DbRecord> {
+        self.database.get_mut(key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &DbRecord)> {
+        self.database.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&String, &mut DbRecord)> {
+        self.database.iter_mut()
+    }
+
     pub fn insert(&mut self, key: String, value: DbRecord) -> Option<DbRecord> {
         self.database.insert(key, value)
     }
@@ -31,27 +71,101 @@ impl Db {
         self.database.remove(key)
     }
 
-    pub fn restore(&mut self) {
+    pub fn restore(&mut self) -> Result<(), String> {
         let database_file_path: String =
             format!("{dir}{file}", dir = self.data_dir, file = "db.dat");
 
         if Path::new(&database_file_path).exists() {
-            let compressed: Vec<u8> =
-                fs::read(database_file_path).expect("Failed to read from database");
-            let uncompressed: Vec<u8> = decompress_size_prepended(&compressed).unwrap();
-            let serialized: String =
-                String::from_utf8(uncompressed).expect("Failed to read from database");
+            let compressed: Vec<u8> = fs::read(&database_file_path)
+                .map_err(|x| format!("Can not read database {}: {}", database_file_path, x))?;
+            let uncompressed: Vec<u8> = decompress_size_prepended(&compressed)
+                .map_err(|x| format!("Can not decompress database: {}", x))?;
+            let serialized: String = String::from_utf8(uncompressed)
+                .map_err(|x| format!("Can not read database: {}", x))?;
+
+            match serde_json::from_str::<DbFile<HashMap<String, DbRecord>>>(&serialized) {
+                Ok(db_file) => {
+                    self.chain = db_file.chain;
+                    self.database = db_file.database;
+                }
+                Err(x) => {
+                    self.database = serde_json::from_str(serialized.as_str()).map_err(|_| {
+                        format!("Can not parse database {}: {}", database_file_path, x)
+                    })?
+                }
+            }
 
-            self.database = serde_json::from_str(serialized.as_str()).unwrap();
+            for db_record in self.database.values_mut() {
+                for statistic in db_record.statistics_mut() {
+                    statistic.backfill_mem_used_percent();
+                }
+            }
         }
+
+        Ok(())
     }
 
-    pub fn save(&self) {
+    pub fn save(&mut self) {
+        let start: Instant = Instant::now();
         let database_file_path: String =
             format!("{dir}{file}", dir = self.data_dir, file = "db.dat");
-        let serialized: Vec<u8> = serde_json::to_vec(&self.database).unwrap();
+
+        self.chain
+            .set_signature(self.de.sign(&self.chain.signed_data()));
+
+        let db_file: DbFile<&HashMap<String, DbRecord>> = DbFile {
+            chain: self.chain.clone(),
+            database: &self.database,
+        };
+        let serialized: Vec<u8> = serde_json::to_vec(&db_file).unwrap();
         let compressed: Vec<u8> = compress_prepend_size(&serialized);
+        common_file::write_atomic(&database_file_path, &compressed)
+            .expect("Failed to write to database");
+
+        self.save_count += 1;
+        self.save_duration_total += start.elapsed();
+        self.save_size = compressed.len();
+    }
+
+    pub fn save_count(&self) -> &u64 {
+        &self.save_count
+    }
+
+    pub fn save_duration_total(&self) -> &Duration {
+        &self.save_duration_total
+    }
+
+    pub fn save_size(&self) -> &usize {
+        &self.save_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+    use rsa::{RsaPrivateKey, RsaPublicKey};
+
+    #[test]
+    fn restore_returns_error_for_unparsable_database() {
+        let private_key: RsaPrivateKey = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let public_key: RsaPublicKey = RsaPublicKey::from(&private_key);
+        let data_dir: String = format!(
+            "{}/auditor_db_{}/",
+            std::env::temp_dir().display(),
+            std::process::id()
+        );
+        fs::create_dir_all(&data_dir).unwrap();
+        fs::write(
+            format!("{}db.dat", data_dir),
+            compress_prepend_size(b"{\"host\": {\"statistics\": null}}"),
+        )
+        .unwrap();
+
+        let result: Result<(), String> =
+            Db::new(data_dir.to_string(), De::new(None, private_key, public_key)).restore();
+        fs::remove_dir_all(&data_dir).unwrap();
 
-        fs::write(database_file_path, compressed).expect("Failed to write to database");
+        assert!(result.unwrap_err().starts_with("Can not parse database"));
     }
 }
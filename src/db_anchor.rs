@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DbAnchor {
+    receipt: String,
+    sink: String,
+    submitted_at: u64,
+}
+
+impl DbAnchor {
+    pub fn new(receipt: String, sink: String, submitted_at: u64) -> Self {
+        Self {
+            receipt,
+            sink,
+            submitted_at,
+        }
+    }
+
+    pub fn sink(&self) -> &String {
+        &self.sink
+    }
+}
@@ -0,0 +1,305 @@
+use crate::config::Config;
+use crate::db::Db;
+use crate::db_statistics::DbStatistics;
+use crate::de;
+use crate::de::De;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct DbChain {
+    head: String,
+    sequence: u64,
+    #[serde(default)]
+    signature: String,
+}
+
+impl DbChain {
+    pub fn new(head: String, sequence: u64, signature: String) -> Self {
+        Self {
+            head,
+            sequence,
+            signature,
+        }
+    }
+
+    pub fn head(&self) -> &String {
+        &self.head
+    }
+
+    pub fn sequence(&self) -> &u64 {
+        &self.sequence
+    }
+
+    pub fn signature(&self) -> &String {
+        &self.signature
+    }
+
+    pub fn set_signature(&mut self, signature: String) {
+        self.signature = signature;
+    }
+
+    pub fn signed_data(&self) -> String {
+        format!("chain.{}.{}", self.sequence, self.head)
+    }
+}
+
+pub fn link(database: &mut Db, host_id: &str, statistic: &mut DbStatistics) {
+    let previous_hash: String = database
+        .get(host_id)
+        .and_then(|db_record| {
+            db_record
+                .statistics()
+                .iter()
+                .rev()
+                .find(|statistic| !statistic.blockchain_hash().is_empty())
+        })
+        .map(|statistic| statistic.blockchain_hash().to_string())
+        .unwrap_or_default();
+    let sequence: u64 = database.chain().sequence() + 1;
+
+    statistic.set_sequence(sequence);
+
+    let blockchain_hash: String = hash_statistic(&previous_hash, statistic);
+    let head: String = hash_head(database.chain().head(), &blockchain_hash);
+
+    statistic.set_blockchain_hash(blockchain_hash);
+    database.set_chain(DbChain::new(head, sequence, String::new()));
+}
+
+pub fn check(database: &Db, public_key: &str) -> Vec<String> {
+    let mut errors: Vec<String> = vec![];
+    let mut chained: Vec<(u64, &String)> = vec![];
+
+    for (host_id, db_record) in database.iter() {
+        let mut previous_hash: String = String::new();
+
+        for statistic in db_record.statistics() {
+            if statistic.blockchain_hash().is_empty() {
+                if !previous_hash.is_empty() {
+                    errors.push(format!(
+                        "Host {} sample {} is missing from the chain",
+                        host_id,
+                        statistic.id()
+                    ));
+                }
+
+                continue;
+            }
+
+            if hash_statistic(&previous_hash, statistic) != *statistic.blockchain_hash() {
+                errors.push(format!(
+                    "Host {} sample {} does not match its hash",
+                    host_id,
+                    statistic.id()
+                ));
+            }
+
+            previous_hash = statistic.blockchain_hash().to_string();
+            chained.push((*statistic.sequence(), statistic.blockchain_hash()));
+        }
+    }
+
+    chained.sort();
+
+    let mut head: String = String::new();
+
+    for (index, (sequence, blockchain_hash)) in chained.iter().enumerate() {
+        if *sequence != index as u64 + 1 {
+            errors.push(format!(
+                "Sequence {} found where {} was expected",
+                sequence,
+                index + 1
+            ));
+            break;
+        }
+
+        head = hash_head(&head, blockchain_hash);
+    }
+
+    if chained.len() as u64 != *database.chain().sequence() {
+        errors.push(format!(
+            "Chain has {} samples but the head sequence is {}",
+            chained.len(),
+            database.chain().sequence()
+        ));
+    } else if head != *database.chain().head() {
+        errors.push(format!(
+            "Chain head {} does not match the recorded head {}",
+            head,
+            database.chain().head()
+        ));
+    }
+
+    if !check_signature(database.chain(), public_key) {
+        errors.push(String::from("Chain head signature is not valid"));
+    }
+
+    errors
+}
+
+pub fn check_signature(chain: &DbChain, public_key: &str) -> bool {
+    *chain.sequence() == 0 || de::check_sign(&chain.signed_data(), public_key, chain.signature())
+}
+
+pub fn run_integrity_check(
+    config: Config,
+    de: De,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut database: Db = Db::new(config.data_dir().to_string(), de.clone());
+    database.restore()?;
+
+    let errors: Vec<String> = check(&database, &de.public_key_string());
+
+    for error in &errors {
+        println!("{}", error);
+    }
+
+    if !errors.is_empty() {
+        return Err(format!("Integrity check failed with {} errors", errors.len()).into());
+    }
+
+    println!("head = {}", database.chain().head());
+    println!("sequence = {}", database.chain().sequence());
+
+    Ok(())
+}
+
+pub fn canonical(statistic: &DbStatistics) -> String {
+    let mut statistic: DbStatistics = statistic.clone();
+    statistic.set_blockchain_hash(String::new());
+    statistic.set_used_for_proof(false);
+
+    serde_json::to_string(&statistic).unwrap()
+}
+
+fn hash_head(previous_head: &str, blockchain_hash: &str) -> String {
+    de::digest(&format!("{}.{}", previous_head, blockchain_hash))
+}
+
+fn hash_statistic(previous_hash: &str, statistic: &DbStatistics) -> String {
+    de::digest(&format!("{}.{}", previous_hash, canonical(statistic)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_record::DbRecord;
+    use rand::rngs::OsRng;
+    use rsa::{RsaPrivateKey, RsaPublicKey};
+
+    fn get_de() -> De {
+        let private_key: RsaPrivateKey = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let public_key: RsaPublicKey = RsaPublicKey::from(&private_key);
+
+        De::new(None, private_key, public_key)
+    }
+
+    fn get_statistic(id: &str, timestamp: u64) -> DbStatistics {
+        serde_json::from_value(serde_json::json!({
+            "blockchain_hash": "",
+            "cpu_usage": 50.0,
+            "id": id,
+            "mt_2_result": 0.1,
+            "mt_4_result": 0.1,
+            "mt_8_result": 0.1,
+            "st_result": 0.1,
+            "timestamp": timestamp,
+            "used_for_proof": false,
+        }))
+        .unwrap()
+    }
+
+    fn push(database: &mut Db, host_id: &str, id: &str, timestamp: u64) {
+        let mut statistic: DbStatistics = get_statistic(id, timestamp);
+
+        link(database, host_id, &mut statistic);
+
+        match database.get_mut(host_id) {
+            Some(db_record) => db_record.statistics_mut().push(statistic),
+            None => {
+                database.insert(
+                    host_id.to_string(),
+                    DbRecord::new(vec![], String::new(), vec![statistic]),
+                );
+            }
+        }
+    }
+
+    fn sign(database: &mut Db, de: &De) {
+        let mut chain: DbChain = database.chain().clone();
+        chain.set_signature(de.sign(&chain.signed_data()));
+        database.set_chain(chain);
+    }
+
+    fn get_database(de: &De) -> Db {
+        let mut database: Db = Db::new(String::new(), de.clone());
+
+        push(&mut database, "a", "a1", 10);
+        push(&mut database, "b", "b1", 11);
+        push(&mut database, "a", "a2", 20);
+        sign(&mut database, de);
+
+        database
+    }
+
+    #[test]
+    fn check_accepts_signed_chain() {
+        let de: De = get_de();
+        let database: Db = get_database(&de);
+
+        assert_eq!(*database.chain().sequence(), 3);
+        assert!(check(&database, &de.public_key_string()).is_empty());
+    }
+
+    #[test]
+    fn check_rejects_modified_statistic() {
+        let de: De = get_de();
+        let mut database: Db = get_database(&de);
+
+        database.get_mut("a").unwrap().statistics_mut()[0].set_sequence(7);
+
+        let errors: Vec<String> = check(&database, &de.public_key_string());
+
+        assert!(errors
+            .iter()
+            .any(|error| error.contains("does not match its hash")));
+    }
+
+    #[test]
+    fn check_rejects_rewritten_chain_without_key() {
+        let de: De = get_de();
+        let database: Db = get_database(&de);
+        let mut rewritten: Db = Db::new(String::new(), de.clone());
+
+        push(&mut rewritten, "a", "a1", 10);
+        push(&mut rewritten, "b", "b1", 11);
+        push(&mut rewritten, "a", "forged", 20);
+
+        let mut chain: DbChain = rewritten.chain().clone();
+        chain.set_signature(database.chain().signature().to_string());
+        rewritten.set_chain(chain);
+
+        assert_eq!(
+            check(&rewritten, &de.public_key_string()),
+            vec![String::from("Chain head signature is not valid")]
+        );
+    }
+
+    #[test]
+    fn check_rejects_missing_sample() {
+        let de: De = get_de();
+        let mut database: Db = get_database(&de);
+
+        database.get_mut("b").unwrap().statistics_mut().clear();
+
+        assert!(!check(&database, &de.public_key_string()).is_empty());
+    }
+
+    #[test]
+    fn check_accepts_empty_unsigned_chain() {
+        let de: De = get_de();
+        let database: Db = Db::new(String::new(), de.clone());
+
+        assert!(check(&database, &de.public_key_string()).is_empty());
+    }
+}
@@ -0,0 +1,70 @@
+use crate::db_anchor::DbAnchor;
+use crate::response_get_proof_of_computation::ProofOfComputationData;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DbEpoch {
+    #[serde(default)]
+    anchors: Vec<DbAnchor>,
+    data: Vec<ProofOfComputationData>,
+    epoch: u64,
+    from: u64,
+    #[serde(default)]
+    merkle_root: String,
+    proof_of_computation: f64,
+    to: u64,
+}
+
+impl DbEpoch {
+    pub fn new(
+        anchors: Vec<DbAnchor>,
+        data: Vec<ProofOfComputationData>,
+        epoch: u64,
+        from: u64,
+        merkle_root: String,
+        proof_of_computation: f64,
+        to: u64,
+    ) -> Self {
+        Self {
+            anchors,
+            data,
+            epoch,
+            from,
+            merkle_root,
+            proof_of_computation,
+            to,
+        }
+    }
+
+    pub fn anchors(&self) -> &Vec<DbAnchor> {
+        &self.anchors
+    }
+
+    pub fn anchors_mut(&mut self) -> &mut Vec<DbAnchor> {
+        &mut self.anchors
+    }
+
+    pub fn data(&self) -> &Vec<ProofOfComputationData> {
+        &self.data
+    }
+
+    pub fn epoch(&self) -> &u64 {
+        &self.epoch
+    }
+
+    pub fn from(&self) -> &u64 {
+        &self.from
+    }
+
+    pub fn merkle_root(&self) -> &String {
+        &self.merkle_root
+    }
+
+    pub fn proof_of_computation(&self) -> &f64 {
+        &self.proof_of_computation
+    }
+
+    pub fn to(&self) -> &u64 {
+        &self.to
+    }
+}
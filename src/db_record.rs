@@ -1,25 +1,45 @@
+use crate::db_epoch::DbEpoch;
 use crate::db_statistics::DbStatistics;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct DbRecord {
+    #[serde(default)]
+    epochs: Vec<DbEpoch>,
     public_key: String,
     statistics: Vec<DbStatistics>,
 }
 
 impl DbRecord {
-    pub fn new(public_key: String, statistics: Vec<DbStatistics>) -> Self {
+    pub fn new(epochs: Vec<DbEpoch>, public_key: String, statistics: Vec<DbStatistics>) -> Self {
         Self {
+            epochs,
             public_key,
             statistics,
         }
     }
 
+    pub fn epochs(&self) -> &Vec<DbEpoch> {
+        &self.epochs
+    }
+
+    pub fn epochs_mut(&mut self) -> &mut Vec<DbEpoch> {
+        &mut self.epochs
+    }
+
     pub fn public_key(&self) -> &String {
         &self.public_key
     }
 
-    pub fn statistics(&mut self) -> &mut Vec<DbStatistics> {
+    pub fn set_public_key(&mut self, public_key: String) {
+        self.public_key = public_key;
+    }
+
+    pub fn statistics(&self) -> &Vec<DbStatistics> {
+        &self.statistics
+    }
+
+    pub fn statistics_mut(&mut self) -> &mut Vec<DbStatistics> {
         &mut self.statistics
     }
 }
@@ -1,58 +1,116 @@
+use crate::common_metric::Metrics;
+use crate::common_statistics_container::ContainerStatistics;
+use crate::common_statistics_cpu::CpuCoreStatistics;
+use crate::common_statistics_disk::{DiskIoStatistics, DiskMountStatistics};
+use crate::common_statistics_network::NetworkInterfaceStatistics;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct DbStatistics {
     blockchain_hash: String,
-    cpu_count: u16,
-    cpu_idle: f32,
-    cpu_interrupt: f32,
-    cpu_nice: f32,
-    cpu_system: f32,
+    #[serde(default)]
+    container: Option<ContainerStatistics>,
+    #[serde(default)]
+    containerized: bool,
+    #[serde(default)]
+    cpu_cores: Vec<CpuCoreStatistics>,
+    #[serde(default)]
+    cpu_count: Option<u16>,
+    #[serde(default)]
+    cpu_idle: Option<f32>,
+    #[serde(default)]
+    cpu_interrupt: Option<f32>,
+    #[serde(default)]
+    cpu_nice: Option<f32>,
+    #[serde(default)]
+    cpu_system: Option<f32>,
     cpu_usage: f32,
-    cpu_user: f32,
+    #[serde(default)]
+    cpu_user: Option<f32>,
+    #[serde(default)]
+    disk_io: Vec<DiskIoStatistics>,
+    #[serde(default)]
+    disk_mounts: Vec<DiskMountStatistics>,
     id: String,
-    mem_free: u64,
-    mem_usage: f32,
-    mem_total: u64,
+    #[serde(default)]
+    mem_available: Option<u64>,
+    #[serde(default)]
+    mem_free: Option<u64>,
+    #[serde(default)]
+    mem_usage: Option<f32>,
+    #[serde(default)]
+    mem_total: Option<u64>,
+    #[serde(default)]
+    mem_used_percent: Option<f32>,
+    #[serde(default)]
+    mem_used_percent_estimated: bool,
+    #[serde(default)]
+    metrics: Metrics,
+    #[serde(default)]
+    network_interfaces: Vec<NetworkInterfaceStatistics>,
     mt_2_result: f64,
     mt_4_result: f64,
     mt_8_result: f64,
+    #[serde(default = "default_protocol_version")]
+    protocol_version: u32,
+    #[serde(default)]
+    sequence: u64,
     st_result: f64,
-    sys_load_average_fifteen: f32,
-    sys_load_average_five: f32,
-    sys_load_average_one: f32,
-    sys_uptime: f64,
+    #[serde(default)]
+    sys_load_average_fifteen: Option<f32>,
+    #[serde(default)]
+    sys_load_average_five: Option<f32>,
+    #[serde(default)]
+    sys_load_average_one: Option<f32>,
+    #[serde(default)]
+    sys_uptime: Option<f64>,
     timestamp: u64,
     used_for_proof: bool,
 }
 
 impl DbStatistics {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         blockchain_hash: String,
-        cpu_count: u16,
-        cpu_idle: f32,
-        cpu_interrupt: f32,
-        cpu_nice: f32,
-        cpu_system: f32,
+        container: Option<ContainerStatistics>,
+        containerized: bool,
+        cpu_cores: Vec<CpuCoreStatistics>,
+        cpu_count: Option<u16>,
+        cpu_idle: Option<f32>,
+        cpu_interrupt: Option<f32>,
+        cpu_nice: Option<f32>,
+        cpu_system: Option<f32>,
         cpu_usage: f32,
-        cpu_user: f32,
+        cpu_user: Option<f32>,
+        disk_io: Vec<DiskIoStatistics>,
+        disk_mounts: Vec<DiskMountStatistics>,
         id: String,
-        mem_free: u64,
-        mem_usage: f32,
-        mem_total: u64,
+        mem_available: Option<u64>,
+        mem_free: Option<u64>,
+        mem_usage: Option<f32>,
+        mem_total: Option<u64>,
+        mem_used_percent: Option<f32>,
+        mem_used_percent_estimated: bool,
+        metrics: Metrics,
+        network_interfaces: Vec<NetworkInterfaceStatistics>,
         mt_2_result: f64,
         mt_4_result: f64,
         mt_8_result: f64,
+        protocol_version: u32,
+        sequence: u64,
         st_result: f64,
-        sys_load_average_fifteen: f32,
-        sys_load_average_five: f32,
-        sys_load_average_one: f32,
-        sys_uptime: f64,
+        sys_load_average_fifteen: Option<f32>,
+        sys_load_average_five: Option<f32>,
+        sys_load_average_one: Option<f32>,
+        sys_uptime: Option<f64>,
         timestamp: u64,
         used_for_proof: bool,
     ) -> Self {
         Self {
             blockchain_hash,
+            container,
+            containerized,
+            cpu_cores,
             cpu_count,
             cpu_idle,
             cpu_interrupt,
@@ -60,13 +118,22 @@ impl DbStatistics {
             cpu_system,
             cpu_usage,
             cpu_user,
+            disk_io,
+            disk_mounts,
             id,
+            mem_available,
             mem_free,
             mem_usage,
             mem_total,
+            mem_used_percent,
+            mem_used_percent_estimated,
+            metrics,
+            network_interfaces,
             mt_2_result,
             mt_4_result,
             mt_8_result,
+            protocol_version,
+            sequence,
             st_result,
             sys_load_average_fifteen,
             sys_load_average_five,
@@ -77,16 +144,32 @@ impl DbStatistics {
         }
     }
 
+    pub fn blockchain_hash(&self) -> &String {
+        &self.blockchain_hash
+    }
+
+    pub fn cpu_cores(&self) -> &Vec<CpuCoreStatistics> {
+        &self.cpu_cores
+    }
+
     pub fn cpu_usage(&self) -> &f32 {
         &self.cpu_usage
     }
 
+    pub fn disk_io(&self) -> &Vec<DiskIoStatistics> {
+        &self.disk_io
+    }
+
     pub fn id(&self) -> &String {
         &self.id
     }
 
-    pub fn mem_usage(&self) -> &f32 {
-        &self.mem_usage
+    pub fn mem_used_percent(&self) -> &Option<f32> {
+        &self.mem_used_percent
+    }
+
+    pub fn network_interfaces(&self) -> &Vec<NetworkInterfaceStatistics> {
+        &self.network_interfaces
     }
 
     pub fn mt_2_result(&self) -> &f64 {
@@ -101,27 +184,60 @@ impl DbStatistics {
         &self.mt_8_result
     }
 
+    pub fn sequence(&self) -> &u64 {
+        &self.sequence
+    }
+
     pub fn st_result(&self) -> &f64 {
         &self.st_result
     }
 
-    pub fn sys_load_average_fifteen(&self) -> &f32 {
+    pub fn sys_load_average_fifteen(&self) -> &Option<f32> {
         &self.sys_load_average_fifteen
     }
 
-    pub fn sys_load_average_five(&self) -> &f32 {
+    pub fn sys_load_average_five(&self) -> &Option<f32> {
         &self.sys_load_average_five
     }
 
-    pub fn sys_load_average_one(&self) -> &f32 {
+    pub fn sys_load_average_one(&self) -> &Option<f32> {
         &self.sys_load_average_one
     }
 
-    pub fn sys_uptime(&self) -> &f64 {
+    pub fn sys_uptime(&self) -> &Option<f64> {
         &self.sys_uptime
     }
 
+    pub fn timestamp(&self) -> &u64 {
+        &self.timestamp
+    }
+
     pub fn used_for_proof(&self) -> bool {
         self.used_for_proof
     }
+
+    pub fn set_blockchain_hash(&mut self, blockchain_hash: String) {
+        self.blockchain_hash = blockchain_hash;
+    }
+
+    pub fn set_sequence(&mut self, sequence: u64) {
+        self.sequence = sequence;
+    }
+
+    pub fn set_used_for_proof(&mut self, used_for_proof: bool) {
+        self.used_for_proof = used_for_proof;
+    }
+
+    pub fn backfill_mem_used_percent(&mut self) {
+        if let (None, Some(mem_usage)) = (self.mem_used_percent, self.mem_usage) {
+            if mem_usage > 0.0 && mem_usage.is_finite() && self.mem_total != Some(0) {
+                self.mem_used_percent = Some((1.0 - mem_usage) * 100.0);
+                self.mem_used_percent_estimated = true;
+            }
+        }
+    }
+}
+
+fn default_protocol_version() -> u32 {
+    1
 }
@@ -7,6 +7,7 @@ use rsa::{
     pkcs1::ToRsaPublicKey, RsaPrivateKey, RsaPublicKey,
 };
 use sha2::{Digest, Sha256};
+use std::fs;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::Write;
@@ -15,13 +16,19 @@ use std::result::Result;
 
 #[derive(Clone, Debug)]
 pub struct De {
+    previous_private_key: Option<RsaPrivateKey>,
     private_key: RsaPrivateKey,
     public_key: RsaPublicKey,
 }
 
 impl De {
-    pub fn new(private_key: RsaPrivateKey, public_key: RsaPublicKey) -> Self {
+    pub fn new(
+        previous_private_key: Option<RsaPrivateKey>,
+        private_key: RsaPrivateKey,
+        public_key: RsaPublicKey,
+    ) -> Self {
         Self {
+            previous_private_key,
             private_key,
             public_key,
         }
@@ -32,24 +39,38 @@ impl De {
     }
 
     pub fn sign(&self, data: &str) -> String {
-        let mut rng: ThreadRng = rand::thread_rng();
-        let mut hasher: Sha256 = Sha256::new();
-        hasher.update(data);
-        let digest = hasher.finalize();
-        let signature: Vec<u8> =
-            rsa_fdh::sign::<Sha256, _>(&mut rng, &self.private_key, &digest).unwrap();
-
-        encode(signature)
+        sign_with(&self.private_key, data)
     }
+
+    pub fn sign_previous(&self, data: &str) -> Option<String> {
+        self.previous_private_key
+            .as_ref()
+            .map(|previous_private_key| sign_with(previous_private_key, data))
+    }
+}
+
+fn sign_with(private_key: &RsaPrivateKey, data: &str) -> String {
+    let mut rng: ThreadRng = rand::thread_rng();
+    let mut hasher: Sha256 = Sha256::new();
+    hasher.update(data);
+    let digest = hasher.finalize();
+    let signature: Vec<u8> = rsa_fdh::sign::<Sha256, _>(&mut rng, private_key, &digest).unwrap();
+
+    encode(signature)
 }
 
 pub fn check_sign(data: &str, public_key_string: &str, signature_string: &str) -> bool {
     let mut hasher: Sha256 = Sha256::new();
     hasher.update(data);
     let digest = hasher.finalize();
-    let public_key: RsaPublicKey =
-        RsaPublicKey::from_pkcs1_pem(public_key_string).expect("Failed to read key");
-    let signature: Vec<u8> = decode(signature_string).unwrap();
+    let public_key: RsaPublicKey = match RsaPublicKey::from_pkcs1_pem(public_key_string) {
+        Ok(public_key) => public_key,
+        Err(_) => return false,
+    };
+    let signature: Vec<u8> = match decode(signature_string) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
     let verification = rsa_fdh::verify::<Sha256, _>(&public_key, &digest, &signature);
 
     match verification {
@@ -58,12 +79,39 @@ pub fn check_sign(data: &str, public_key_string: &str, signature_string: &str) -
     }
 }
 
+pub fn digest(data: &str) -> String {
+    let mut hasher: Sha256 = Sha256::new();
+    hasher.update(data);
+    let digest = hasher.finalize();
+
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn fingerprint(public_key_string: &str) -> String {
+    digest(public_key_string)
+}
+
+pub fn read_public_key_string(public_key_path: &str) -> Option<String> {
+    let public_key_string: String = fs::read_to_string(public_key_path).ok()?;
+    let public_key: RsaPublicKey = RsaPublicKey::from_pkcs1_pem(&public_key_string).ok()?;
+
+    public_key.to_pkcs1_pem().ok()
+}
+
 pub fn get(config: &Config) -> De {
     let private_key_path: String = format!("{dir}{file}", dir = config.etc_dir(), file = "key");
     let public_key_path: String = format!("{dir}{file}", dir = config.etc_dir(), file = "key.pub");
+    let previous_private_key_path: String =
+        format!("{dir}{file}", dir = config.etc_dir(), file = "key.previous");
     let private_key: RsaPrivateKey = get_private_key(&private_key_path);
     let public_key: RsaPublicKey = get_public_key(&private_key, &public_key_path);
-    De::new(private_key, public_key)
+    let previous_private_key: Option<RsaPrivateKey> =
+        if Path::new(&previous_private_key_path).exists() {
+            Some(get_private_key(&previous_private_key_path))
+        } else {
+            None
+        };
+    De::new(previous_private_key, private_key, public_key)
 }
 
 fn get_private_key(private_key_path: &str) -> RsaPrivateKey {
@@ -73,14 +121,14 @@ fn get_private_key(private_key_path: &str) -> RsaPrivateKey {
         let private_key: RsaPrivateKey =
             RsaPrivateKey::new(&mut rng, bits).expect("Failed to generate a key");
         let mut private_key_file: File =
-            File::create(&private_key_path).expect("Failed to create a file");
+            File::create(private_key_path).expect("Failed to create a file");
         private_key_file
             .write_all(private_key.to_pkcs1_pem().unwrap().as_bytes())
             .expect("Failed to write key");
         private_key
     } else {
         let mut private_key_file: File =
-            File::open(&private_key_path).expect("Failed to open a file");
+            File::open(private_key_path).expect("Failed to open a file");
         let mut private_key_contents: String = String::new();
         private_key_file
             .read_to_string(&mut private_key_contents)
@@ -95,14 +143,13 @@ fn get_public_key(private_key: &RsaPrivateKey, public_key_path: &str) -> RsaPubl
     if !Path::new(public_key_path).exists() {
         let public_key: RsaPublicKey = RsaPublicKey::from(private_key);
         let mut public_key_file: File =
-            File::create(&public_key_path).expect("Failed to create a file");
+            File::create(public_key_path).expect("Failed to create a file");
         public_key_file
             .write_all(public_key.to_pkcs1_pem().unwrap().as_bytes())
             .expect("Failed to write key");
         public_key
     } else {
-        let mut public_key_file: File =
-            File::open(&public_key_path).expect("Failed to open a file");
+        let mut public_key_file: File = File::open(public_key_path).expect("Failed to open a file");
         let mut public_key_string: String = String::new();
         public_key_file
             .read_to_string(&mut public_key_string)
@@ -2,53 +2,112 @@ use crate::config::Config;
 use crate::de::De;
 use crate::info::Info;
 use crate::mode::Mode;
-use clap::{App, Arg, ArgMatches};
+use clap::{App, Arg, ArgGroup, ArgMatches, SubCommand};
 use log::SetLoggerError;
 use std::process;
 
 mod client;
 mod client_benchmark;
+mod client_cgroup;
+mod client_collector;
+mod client_collector_process;
 mod client_config;
 mod client_requests;
 mod client_statistics;
+mod client_status;
+mod common_certificate;
+mod common_config;
+mod common_file;
 mod common_log;
+mod common_metric;
 mod common_request;
+mod common_signal;
+mod common_statistics_container;
+mod common_statistics_cpu;
+mod common_statistics_disk;
+mod common_statistics_network;
+mod common_systemd;
 mod config;
 mod db;
+mod db_anchor;
+mod db_chain;
+mod db_epoch;
 mod db_record;
 mod db_statistics;
 mod de;
 mod info;
 mod mode;
 mod request_post_statistics;
+mod response_get_host;
+mod response_get_hosts;
+mod response_get_inclusion_proof;
 mod response_get_proof_of_computation;
+mod response_get_report;
+mod response_get_statistics;
 mod response_post_statistics;
 mod server;
+mod server_anchor;
+mod server_audit;
 mod server_config;
+mod server_context;
 mod server_handle_requests;
+mod server_liveness;
+mod server_merkle;
+mod server_metrics;
+mod server_proof;
+mod server_report;
+mod server_scoring;
+mod server_webhook;
 
 pub async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let _log: Result<_, SetLoggerError> = common_log::init();
     let info: Info = info::Info::new();
     let cli_parameters: ArgMatches = get_cli_parameters(&info);
-    let config: Config = config::parse(&cli_parameters, &info);
-    let de: De = de::get(&config);
+
+    if let Some(verify_parameters) = cli_parameters.subcommand_matches("verify") {
+        return common_certificate::run_verify(verify_parameters);
+    }
+
+    let config: Config = config::parse(&cli_parameters, &info)?;
 
     if 1 == cli_parameters.occurrences_of("print_configuration") {
         config.print();
+
+        match config.mode() {
+            Mode::Client => client_config::print(&config)?,
+            Mode::ClientLoadSimulator => {}
+            _ => server_config::print(&config)?,
+        }
+
         process::exit(0);
     }
 
+    let de: De = de::get(&config);
+
     match config.mode() {
+        Mode::Audit => server_audit::run(
+            config,
+            de,
+            cli_parameters.subcommand_matches("audit").unwrap(),
+        )?,
         Mode::Client => client::run(config, de, info).await?,
         Mode::ClientLoadSimulator => client::run_load_simulator().await?,
+        Mode::IntegrityCheck => db_chain::run_integrity_check(config, de)?,
+        Mode::Report => {
+            server_report::run(
+                config,
+                de,
+                cli_parameters.subcommand_matches("report").unwrap(),
+            )
+            .await?
+        }
         Mode::Server => server::run(config, de, info).await?,
     }
 
     Ok(())
 }
 
-fn get_cli_parameters(info: &Info) -> ArgMatches {
+fn get_cli_parameters(info: &Info) -> ArgMatches<'static> {
     App::new(info.name())
         .version(info.version().as_str())
         .author("Michal Piotrowski <michal@eventhorizonlabs.eu>")
@@ -81,17 +140,145 @@ fn get_cli_parameters(info: &Info) -> ArgMatches {
                 .help("Sets a custom etc dir")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("log_file")
+                .long("log-file")
+                .value_name("FILE")
+                .help("Writes logs to a file instead of stdout")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("log_format")
+                .long("log-format")
+                .value_name("FORMAT")
+                .help("Sets the log format (text or json)")
+                .possible_values(&["json", "text"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("log_level")
+                .long("log-level")
+                .value_name("LEVELS")
+                .help("Sets log levels, e.g. info,auditor::server_anchor=debug")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("print_configuration")
                 .short("P")
                 .long("print-configuration")
                 .help("Prints configuration information"),
         )
+        .arg(
+            Arg::with_name("set")
+                .short("s")
+                .long("set")
+                .value_name("KEY=VALUE")
+                .help("Overrides a configuration value, e.g. webhooks.max_attempts=3")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
         .arg(
             Arg::with_name("server_mode")
                 .short("S")
                 .long("server-mode")
                 .help("Run in server mode"),
         )
+        .subcommand(
+            SubCommand::with_name("audit")
+                .about("Queries or verifies the signed audit log")
+                .arg(
+                    Arg::with_name("event")
+                        .long("event")
+                        .value_name("EVENT")
+                        .help("Shows only entries of this event type")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .value_name("TIMESTAMP")
+                        .help("Shows only entries issued at or after this time")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("host")
+                        .long("host")
+                        .value_name("HOST_ID")
+                        .help("Shows only entries about this host")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .value_name("TIMESTAMP")
+                        .help("Shows only entries issued before this time")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("verify")
+                        .long("verify")
+                        .help("Verifies the hash chain and signatures of the audit log"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("integrity-check")
+                .about("Verifies the statistics hash chain in the local database"),
+        )
+        .subcommand(
+            SubCommand::with_name("report")
+                .about("Prints a signed proof of computation report for all hosts")
+                .arg(
+                    Arg::with_name("epoch")
+                        .long("epoch")
+                        .value_name("EPOCH")
+                        .help("Sets the epoch to report on")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .value_name("TIMESTAMP")
+                        .help("Sets the start of the window")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .value_name("TIMESTAMP")
+                        .help("Sets the end of the window")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about("Verifies a signed certificate without network access")
+                .arg(
+                    Arg::with_name("file")
+                        .value_name("FILE")
+                        .help("Sets the certificate file to verify")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("fingerprint")
+                        .long("fingerprint")
+                        .value_name("FINGERPRINT")
+                        .help("Sets the fingerprint of the trusted auditor public key")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("public_key")
+                        .long("public-key")
+                        .value_name("FILE")
+                        .help("Sets the trusted auditor public key file")
+                        .takes_value(true),
+                )
+                .group(
+                    ArgGroup::with_name("trusted_key")
+                        .args(&["fingerprint", "public_key"])
+                        .required(true),
+                ),
+        )
         .get_matches()
 }
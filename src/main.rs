@@ -1,4 +1,11 @@
+use std::process;
+
 #[tokio::main]
 async fn main() {
-    let _result: Result<(), Box<dyn std::error::Error + Send + Sync>> = auditor::run().await;
+    let result: Result<(), Box<dyn std::error::Error + Send + Sync>> = auditor::run().await;
+
+    if let Err(x) = result {
+        eprintln!("Error: {}", x);
+        process::exit(1);
+    }
 }
@@ -1,6 +1,9 @@
 #[derive(Clone, Debug)]
 pub enum Mode {
+    Audit,
     Client,
     ClientLoadSimulator,
+    IntegrityCheck,
+    Report,
     Server,
 }
@@ -1,64 +1,129 @@
+use crate::common_metric::Metrics;
+use crate::common_statistics_container::ContainerStatistics;
+use crate::common_statistics_cpu::CpuCoreStatistics;
+use crate::common_statistics_disk::{DiskIoStatistics, DiskMountStatistics};
+use crate::common_statistics_network::NetworkInterfaceStatistics;
 use serde::{Deserialize, Serialize};
 
+pub const PROTOCOL_VERSION: u32 = 2;
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct RequestPostStatistics {
-    cpu_count: u16,
-    cpu_idle: f32,
-    cpu_interrupt: f32,
-    cpu_nice: f32,
-    cpu_system: f32,
-    cpu_user: f32,
+    #[serde(default)]
+    collected_at: u64,
+    #[serde(default)]
+    container: Option<ContainerStatistics>,
+    #[serde(default)]
+    containerized: bool,
+    #[serde(default)]
+    cpu_cores: Vec<CpuCoreStatistics>,
+    #[serde(default)]
+    cpu_count: Option<u16>,
+    #[serde(default)]
+    cpu_idle: Option<f32>,
+    #[serde(default)]
+    cpu_interrupt: Option<f32>,
+    #[serde(default)]
+    cpu_nice: Option<f32>,
+    #[serde(default)]
+    cpu_system: Option<f32>,
+    #[serde(default)]
+    cpu_user: Option<f32>,
+    #[serde(default)]
+    disk_io: Vec<DiskIoStatistics>,
+    #[serde(default)]
+    disk_mounts: Vec<DiskMountStatistics>,
     host_id: String,
-    mem_free: u64,
-    mem_usage: f32,
-    mem_total: u64,
+    #[serde(default)]
+    mem_available: Option<u64>,
+    #[serde(default)]
+    mem_free: Option<u64>,
+    #[serde(default)]
+    mem_usage: Option<f32>,
+    #[serde(default)]
+    mem_total: Option<u64>,
+    #[serde(default)]
+    mem_used_percent: Option<f32>,
+    #[serde(default)]
+    metrics: Metrics,
+    #[serde(default)]
+    network_interfaces: Vec<NetworkInterfaceStatistics>,
     mt_2_result: f64,
     mt_4_result: f64,
     mt_8_result: f64,
+    #[serde(default = "default_protocol_version")]
+    protocol_version: u32,
     public_key: String,
     st_result: f64,
-    sys_load_average_fifteen: f32,
-    sys_load_average_five: f32,
-    sys_load_average_one: f32,
-    sys_uptime: f64,
+    #[serde(default)]
+    sys_load_average_fifteen: Option<f32>,
+    #[serde(default)]
+    sys_load_average_five: Option<f32>,
+    #[serde(default)]
+    sys_load_average_one: Option<f32>,
+    #[serde(default)]
+    sys_uptime: Option<f64>,
 }
 
 impl RequestPostStatistics {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        cpu_count: u16,
-        cpu_idle: f32,
-        cpu_interrupt: f32,
-        cpu_nice: f32,
-        cpu_system: f32,
-        cpu_user: f32,
+        collected_at: u64,
+        container: Option<ContainerStatistics>,
+        containerized: bool,
+        cpu_cores: Vec<CpuCoreStatistics>,
+        cpu_count: Option<u16>,
+        cpu_idle: Option<f32>,
+        cpu_interrupt: Option<f32>,
+        cpu_nice: Option<f32>,
+        cpu_system: Option<f32>,
+        cpu_user: Option<f32>,
+        disk_io: Vec<DiskIoStatistics>,
+        disk_mounts: Vec<DiskMountStatistics>,
         host_id: String,
-        mem_free: u64,
-        mem_usage: f32,
-        mem_total: u64,
+        mem_available: Option<u64>,
+        mem_free: Option<u64>,
+        mem_usage: Option<f32>,
+        mem_total: Option<u64>,
+        mem_used_percent: Option<f32>,
+        metrics: Metrics,
+        network_interfaces: Vec<NetworkInterfaceStatistics>,
         mt_2_result: f64,
         mt_4_result: f64,
         mt_8_result: f64,
+        protocol_version: u32,
         public_key: String,
         st_result: f64,
-        sys_load_average_fifteen: f32,
-        sys_load_average_five: f32,
-        sys_load_average_one: f32,
-        sys_uptime: f64,
+        sys_load_average_fifteen: Option<f32>,
+        sys_load_average_five: Option<f32>,
+        sys_load_average_one: Option<f32>,
+        sys_uptime: Option<f64>,
     ) -> Self {
         Self {
+            collected_at,
+            container,
+            containerized,
+            cpu_cores,
             cpu_count,
             cpu_idle,
             cpu_interrupt,
             cpu_nice,
             cpu_system,
             cpu_user,
+            disk_io,
+            disk_mounts,
             host_id,
+            mem_available,
             mem_free,
             mem_usage,
             mem_total,
+            mem_used_percent,
+            metrics,
+            network_interfaces,
             mt_2_result,
             mt_4_result,
             mt_8_result,
+            protocol_version,
             public_key,
             st_result,
             sys_load_average_fifteen,
@@ -68,46 +133,82 @@ impl RequestPostStatistics {
         }
     }
 
-    pub fn cpu_count(&self) -> &u16 {
+    pub fn collected_at(&self) -> &u64 {
+        &self.collected_at
+    }
+
+    pub fn container(&self) -> &Option<ContainerStatistics> {
+        &self.container
+    }
+
+    pub fn containerized(&self) -> &bool {
+        &self.containerized
+    }
+
+    pub fn cpu_cores(&self) -> &Vec<CpuCoreStatistics> {
+        &self.cpu_cores
+    }
+
+    pub fn cpu_count(&self) -> &Option<u16> {
         &self.cpu_count
     }
 
-    pub fn cpu_idle(&self) -> &f32 {
+    pub fn cpu_idle(&self) -> &Option<f32> {
         &self.cpu_idle
     }
 
-    pub fn cpu_interrupt(&self) -> &f32 {
+    pub fn cpu_interrupt(&self) -> &Option<f32> {
         &self.cpu_interrupt
     }
 
-    pub fn cpu_nice(&self) -> &f32 {
+    pub fn cpu_nice(&self) -> &Option<f32> {
         &self.cpu_nice
     }
 
-    pub fn cpu_system(&self) -> &f32 {
+    pub fn cpu_system(&self) -> &Option<f32> {
         &self.cpu_system
     }
 
-    pub fn cpu_user(&self) -> &f32 {
+    pub fn cpu_user(&self) -> &Option<f32> {
         &self.cpu_user
     }
 
+    pub fn disk_io(&self) -> &Vec<DiskIoStatistics> {
+        &self.disk_io
+    }
+
+    pub fn disk_mounts(&self) -> &Vec<DiskMountStatistics> {
+        &self.disk_mounts
+    }
+
     pub fn host_id(&self) -> &String {
         &self.host_id
     }
 
-    pub fn mem_free(&self) -> &u64 {
+    pub fn mem_available(&self) -> &Option<u64> {
+        &self.mem_available
+    }
+
+    pub fn mem_free(&self) -> &Option<u64> {
         &self.mem_free
     }
 
-    pub fn mem_usage(&self) -> &f32 {
+    pub fn mem_usage(&self) -> &Option<f32> {
         &self.mem_usage
     }
 
-    pub fn mem_total(&self) -> &u64 {
+    pub fn mem_total(&self) -> &Option<u64> {
         &self.mem_total
     }
 
+    pub fn mem_used_percent(&self) -> &Option<f32> {
+        &self.mem_used_percent
+    }
+
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
     pub fn mt_2_result(&self) -> &f64 {
         &self.mt_2_result
     }
@@ -120,6 +221,14 @@ impl RequestPostStatistics {
         &self.mt_8_result
     }
 
+    pub fn network_interfaces(&self) -> &Vec<NetworkInterfaceStatistics> {
+        &self.network_interfaces
+    }
+
+    pub fn protocol_version(&self) -> &u32 {
+        &self.protocol_version
+    }
+
     pub fn public_key(&self) -> &String {
         &self.public_key
     }
@@ -128,19 +237,23 @@ impl RequestPostStatistics {
         &self.st_result
     }
 
-    pub fn sys_load_average_fifteen(&self) -> &f32 {
+    pub fn sys_load_average_fifteen(&self) -> &Option<f32> {
         &self.sys_load_average_fifteen
     }
 
-    pub fn sys_load_average_five(&self) -> &f32 {
+    pub fn sys_load_average_five(&self) -> &Option<f32> {
         &self.sys_load_average_five
     }
 
-    pub fn sys_load_average_one(&self) -> &f32 {
+    pub fn sys_load_average_one(&self) -> &Option<f32> {
         &self.sys_load_average_one
     }
 
-    pub fn sys_uptime(&self) -> &f64 {
+    pub fn sys_uptime(&self) -> &Option<f64> {
         &self.sys_uptime
     }
 }
+
+fn default_protocol_version() -> u32 {
+    1
+}
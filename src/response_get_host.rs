@@ -0,0 +1,19 @@
+use crate::response_get_hosts::HostData;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct ResponseGetHost {
+    data: HostData,
+    host_id: String,
+    public_key: String,
+}
+
+impl ResponseGetHost {
+    pub fn new(data: HostData, host_id: String, public_key: String) -> Self {
+        Self {
+            data,
+            host_id,
+            public_key,
+        }
+    }
+}
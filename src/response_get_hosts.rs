@@ -0,0 +1,59 @@
+use crate::server_liveness::LivenessState;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct HostData {
+    expected_interval: u64,
+    first_seen: Option<u64>,
+    host_id: String,
+    last_seen: Option<u64>,
+    liveness: LivenessState,
+    public_key_fingerprint: String,
+    sample_count: usize,
+}
+
+impl HostData {
+    pub fn new(
+        expected_interval: u64,
+        first_seen: Option<u64>,
+        host_id: String,
+        last_seen: Option<u64>,
+        liveness: LivenessState,
+        public_key_fingerprint: String,
+        sample_count: usize,
+    ) -> Self {
+        Self {
+            expected_interval,
+            first_seen,
+            host_id,
+            last_seen,
+            liveness,
+            public_key_fingerprint,
+            sample_count,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResponseGetHosts {
+    data: Vec<HostData>,
+    host_id: String,
+    next_cursor: Option<String>,
+    public_key: String,
+}
+
+impl ResponseGetHosts {
+    pub fn new(
+        data: Vec<HostData>,
+        host_id: String,
+        next_cursor: Option<String>,
+        public_key: String,
+    ) -> Self {
+        Self {
+            data,
+            host_id,
+            next_cursor,
+            public_key,
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use crate::db_statistics::DbStatistics;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct MerklePathStep {
+    hash: String,
+    position: String,
+}
+
+impl MerklePathStep {
+    pub fn new(hash: String, position: String) -> Self {
+        Self { hash, position }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResponseGetInclusionProof {
+    data: DbStatistics,
+    epoch: Option<u64>,
+    from: u64,
+    host_id: String,
+    index: usize,
+    leaf: String,
+    merkle_root: String,
+    path: Vec<MerklePathStep>,
+    public_key: String,
+    to: u64,
+}
+
+impl ResponseGetInclusionProof {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        data: DbStatistics,
+        epoch: Option<u64>,
+        from: u64,
+        host_id: String,
+        index: usize,
+        leaf: String,
+        merkle_root: String,
+        path: Vec<MerklePathStep>,
+        public_key: String,
+        to: u64,
+    ) -> Self {
+        Self {
+            data,
+            epoch,
+            from,
+            host_id,
+            index,
+            leaf,
+            merkle_root,
+            path,
+            public_key,
+            to,
+        }
+    }
+}
@@ -1,6 +1,6 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ProofOfComputationData {
     id: String,
     partial_proof_of_computation: f64,
@@ -14,6 +14,10 @@ impl ProofOfComputationData {
         }
     }
 
+    pub fn id(&self) -> &String {
+        &self.id
+    }
+
     pub fn partial_proof_of_computation(&self) -> &f64 {
         &self.partial_proof_of_computation
     }
@@ -22,23 +26,42 @@ impl ProofOfComputationData {
 #[derive(Debug, Serialize)]
 pub struct ResponseGetProofOfComputation {
     data: Vec<ProofOfComputationData>,
+    epoch: Option<u64>,
+    from: u64,
+    frozen: bool,
     host_id: String,
+    merkle_root: String,
     proof_of_computation: f64,
     public_key: String,
+    sample_count: usize,
+    to: u64,
 }
 
 impl ResponseGetProofOfComputation {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         data: Vec<ProofOfComputationData>,
+        epoch: Option<u64>,
+        from: u64,
+        frozen: bool,
         host_id: String,
+        merkle_root: String,
         proof_of_computation: f64,
         public_key: String,
+        sample_count: usize,
+        to: u64,
     ) -> Self {
         Self {
             data,
+            epoch,
+            from,
+            frozen,
             host_id,
+            merkle_root,
             proof_of_computation,
             public_key,
+            sample_count,
+            to,
         }
     }
 }
@@ -0,0 +1,78 @@
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct ReportGap {
+    from: u64,
+    to: u64,
+}
+
+impl ReportGap {
+    pub fn new(from: u64, to: u64) -> Self {
+        Self { from, to }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportData {
+    gaps: Vec<ReportGap>,
+    host_id: String,
+    proof_of_computation: f64,
+    sample_count: usize,
+    share: f64,
+}
+
+impl ReportData {
+    pub fn new(
+        gaps: Vec<ReportGap>,
+        host_id: String,
+        proof_of_computation: f64,
+        sample_count: usize,
+        share: f64,
+    ) -> Self {
+        Self {
+            gaps,
+            host_id,
+            proof_of_computation,
+            sample_count,
+            share,
+        }
+    }
+
+    pub fn proof_of_computation(&self) -> &f64 {
+        &self.proof_of_computation
+    }
+
+    pub fn set_share(&mut self, share: f64) {
+        self.share = share;
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResponseGetReport {
+    data: Vec<ReportData>,
+    from: u64,
+    host_id: String,
+    proof_of_computation: f64,
+    public_key: String,
+    to: u64,
+}
+
+impl ResponseGetReport {
+    pub fn new(
+        data: Vec<ReportData>,
+        from: u64,
+        host_id: String,
+        proof_of_computation: f64,
+        public_key: String,
+        to: u64,
+    ) -> Self {
+        Self {
+            data,
+            from,
+            host_id,
+            proof_of_computation,
+            public_key,
+            to,
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Serialize)]
+pub struct ResponseGetStatistics {
+    data: Vec<Value>,
+    host_id: String,
+    next_cursor: Option<String>,
+    public_key: String,
+}
+
+impl ResponseGetStatistics {
+    pub fn new(
+        data: Vec<Value>,
+        host_id: String,
+        next_cursor: Option<String>,
+        public_key: String,
+    ) -> Self {
+        Self {
+            data,
+            host_id,
+            next_cursor,
+            public_key,
+        }
+    }
+}
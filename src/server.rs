@@ -1,43 +1,317 @@
+use crate::common_log;
+use crate::common_signal::{Reload, Shutdown};
+use crate::common_systemd;
 use crate::config::Config;
 use crate::db::Db;
+use crate::db_chain;
+use crate::db_chain::DbChain;
 use crate::de::De;
 use crate::info::Info;
+use crate::server_anchor;
+use crate::server_audit;
+use crate::server_audit::AuditLog;
 use crate::server_config;
-use crate::server_config::ServerConfig;
+use crate::server_config::{ServerAdminConfig, ServerConfig};
+use crate::server_context::ServerContext;
 use crate::server_handle_requests;
+use crate::server_handle_requests::ValidationRejection;
+use crate::server_liveness;
+use crate::server_metrics::ServerMetrics;
+use crate::server_webhook;
+use crate::server_webhook::Webhooks;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{Body, Method, Request, Response, Server, StatusCode};
-use log::info;
-use std::net::{Ipv4Addr, SocketAddr};
+use log::{error, info, warn};
+use serde_json::Value;
+use std::net::{Ipv4Addr, SocketAddr, TcpListener};
 use std::str::FromStr;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
 
 async fn handle(
-    config: Config,
-    database: Arc<Mutex<Db>>,
-    de: De,
-    info: Info,
+    context: ServerContext,
+    metrics_enabled: bool,
     request: Request<Body>,
+    server_metrics: Arc<ServerMetrics>,
 ) -> Result<Response<Body>, hyper::Error> {
-    info!("{} {}", request.method(), request.uri());
+    let webhooks: Webhooks = context.webhooks().clone();
+    let method: Method = request.method().clone();
+    let started: Instant = Instant::now();
+    let path: String = request.uri().path().to_string();
+    let segments: Vec<&str> = path
+        .trim_matches('/')
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
 
-    match (request.method(), request.uri().path()) {
-        (&Method::POST, "/statistics") => {
-            server_handle_requests::handle_post_statistics(config, database, de, info, request)
-                .await
-        }
-        _ => {
-            if request.method() == Method::GET {
+    let (route, response): (&str, Result<Response<Body>, hyper::Error>) =
+        match (request.method(), segments.as_slice()) {
+            (&Method::GET, ["hosts"]) => (
+                "GET /hosts",
+                server_handle_requests::handle_get_hosts(context, request).await,
+            ),
+            (&Method::GET, ["hosts", host_id]) => (
+                "GET /hosts/{host_id}",
+                server_handle_requests::handle_get_host(context, request, host_id.to_string())
+                    .await,
+            ),
+            (&Method::GET, ["hosts", host_id, "statistics"]) => (
+                "GET /hosts/{host_id}/statistics",
+                server_handle_requests::handle_get_host_statistics(
+                    context,
+                    request,
+                    host_id.to_string(),
+                )
+                .await,
+            ),
+            (&Method::GET, ["metrics"]) if metrics_enabled => (
+                "GET /metrics",
+                server_handle_requests::handle_get_metrics(
+                    context.database().clone(),
+                    server_metrics.clone(),
+                )
+                .await,
+            ),
+            (&Method::GET, ["proof-of-computation", host_id]) => (
+                "GET /proof-of-computation/{host_id}",
                 server_handle_requests::handle_get_proof_of_computation(
-                    config, database, de, info, request,
+                    context,
+                    request,
+                    host_id.to_string(),
                 )
-                .await
-            } else {
-                let mut not_found = Response::default();
-                *not_found.status_mut() = StatusCode::NOT_FOUND;
-                Ok(not_found)
+                .await,
+            ),
+            (&Method::GET, ["proof-of-computation", host_id, "inclusion", statistic_id]) => (
+                "GET /proof-of-computation/{host_id}/inclusion/{statistic_id}",
+                server_handle_requests::handle_get_inclusion_proof(
+                    context,
+                    request,
+                    host_id.to_string(),
+                    statistic_id.to_string(),
+                )
+                .await,
+            ),
+            (&Method::GET, ["report"]) => (
+                "GET /report",
+                server_handle_requests::handle_get_report(context, request).await,
+            ),
+            (&Method::POST, ["statistics"]) => (
+                "POST /statistics",
+                server_handle_requests::handle_post_statistics(context, request).await,
+            ),
+            _ => (
+                "not_found",
+                server_handle_requests::handle_not_found(context).await,
+            ),
+        };
+
+    if let Ok(response) = &response {
+        let latency: f64 = started.elapsed().as_secs_f64() * 1000.0;
+        let host_id: Value = match segments.as_slice() {
+            ["hosts", host_id, ..] | ["proof-of-computation", host_id, ..] => Value::from(*host_id),
+            _ => Value::Null,
+        };
+
+        common_log::with_fields(
+            vec![
+                ("latency_ms", Value::from(latency)),
+                ("method", Value::from(method.as_str())),
+                ("path", Value::from(path.as_str())),
+                ("route", Value::from(route)),
+                ("status", Value::from(response.status().as_u16())),
+                ("subject_host_id", host_id),
+            ],
+            || {
+                info!(
+                    "{} {} {} {:.3}ms",
+                    method,
+                    path,
+                    response.status().as_u16(),
+                    latency
+                )
+            },
+        );
+        server_metrics.record_request(route, response.status());
+
+        if response.extensions().get::<ValidationRejection>().is_some() {
+            server_metrics.record_validation_rejection();
+            webhooks.record_validation_rejection();
+        }
+
+        if response.status() == StatusCode::UNAUTHORIZED {
+            server_metrics.record_signature_failure();
+            webhooks.record_signature_failure();
+        }
+    }
+
+    response
+}
+
+async fn handle_admin(
+    database: Arc<Mutex<Db>>,
+    request: Request<Body>,
+    server_metrics: Arc<ServerMetrics>,
+) -> Result<Response<Body>, hyper::Error> {
+    match (request.method(), request.uri().path()) {
+        (&Method::GET, "/metrics") => {
+            server_handle_requests::handle_get_metrics(database, server_metrics).await
+        }
+        _ => Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty())
+            .expect("response builder")),
+    }
+}
+
+async fn reload(audit_log: AuditLog, config: Config, server_config: Arc<RwLock<ServerConfig>>) {
+    let mut reload: Reload = match Reload::new() {
+        Ok(reload) => reload,
+        Err(x) => {
+            warn!("Can not listen for SIGHUP: {}", x);
+            return;
+        }
+    };
+
+    loop {
+        reload.recv().await;
+
+        info!("Received SIGHUP, reloading configuration");
+
+        let new_server_config: ServerConfig = match server_config::parse(&config) {
+            Ok(new_server_config) => new_server_config,
+            Err(x) => {
+                error!("Keeping the previous configuration: {}", x);
+                continue;
+            }
+        };
+        let (new_server_config, restart_required): (ServerConfig, Vec<&str>) =
+            server_config.read().unwrap().reload(new_server_config);
+
+        if let Err(x) = common_log::configure(&config, new_server_config.log()) {
+            error!("Keeping the previous configuration: {}", x);
+            continue;
+        }
+
+        for name in restart_required {
+            warn!(
+                "Changing {} requires a restart, keeping the previous value",
+                name
+            );
+        }
+
+        *server_config.write().unwrap() = new_server_config.clone();
+        audit_log.sync_enrollment(&new_server_config);
+
+        info!("Configuration reloaded");
+    }
+}
+
+async fn notify_systemd(address: SocketAddr, database: Arc<Mutex<Db>>, heartbeat: Arc<AtomicU64>) {
+    let watchdog_interval: Option<Duration> = common_systemd::watchdog_interval();
+    let interval: Duration = watchdog_interval.unwrap_or(Duration::from_secs(10));
+    let mut ready: bool = false;
+
+    loop {
+        let mut states: Vec<String> = vec![];
+
+        if !ready {
+            states.push("READY=1".to_string());
+            ready = true;
+        }
+
+        match check_progress(address, &database, &heartbeat, interval / 2).await {
+            Ok(hosts) => {
+                if watchdog_interval.is_some() {
+                    states.push("WATCHDOG=1".to_string());
+                }
+
+                states.push(format!("STATUS=Serving {} hosts", hosts));
             }
+            Err(x) => {
+                warn!("Withholding watchdog notification: {}", x);
+                states.push(format!("STATUS={}", x));
+            }
+        }
+
+        common_systemd::notify(&states.join("\n"));
+        tokio::time::sleep(interval).await;
+    }
+}
+
+async fn check_progress(
+    address: SocketAddr,
+    database: &Arc<Mutex<Db>>,
+    heartbeat: &AtomicU64,
+    timeout: Duration,
+) -> Result<usize, String> {
+    let deadline: Instant = Instant::now() + timeout;
+    let accepted: u64 = heartbeat.load(Ordering::SeqCst);
+    let _stream: tokio::net::TcpStream =
+        tokio::time::timeout(timeout, tokio::net::TcpStream::connect(address))
+            .await
+            .map_err(|_| String::from("Connecting to the listener timed out"))?
+            .map_err(|x| format!("Can not connect to the listener: {}", x))?;
+
+    while heartbeat.load(Ordering::SeqCst) == accepted {
+        if Instant::now() >= deadline {
+            return Err(String::from("The listener did not accept a connection"));
+        }
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    loop {
+        if let Ok(database) = database.try_lock() {
+            return Ok(database.iter().count());
+        }
+
+        if Instant::now() >= deadline {
+            return Err(String::from("The database lock could not be taken"));
         }
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+fn get_listeners(
+    listeners: Vec<(String, TcpListener)>,
+    admin_enabled: bool,
+) -> Result<(Option<TcpListener>, Option<TcpListener>), String> {
+    if listeners.is_empty() {
+        return Ok((None, None));
+    }
+
+    let named: bool = listeners
+        .iter()
+        .any(|(name, _)| name == "api" || name == "admin");
+    let mut listener: Option<TcpListener> = None;
+    let mut admin_listener: Option<TcpListener> = None;
+
+    for (index, (name, tcp_listener)) in listeners.into_iter().enumerate() {
+        match (named, name.as_str(), index) {
+            (true, "api", _) | (false, _, 0) => listener = Some(tcp_listener),
+            (true, "admin", _) | (false, _, 1) => admin_listener = Some(tcp_listener),
+            _ => warn!("Ignoring socket activated listener {} = {}", index, name),
+        }
+    }
+
+    if listener.is_none() {
+        return Err(String::from(
+            "Socket activation passed no listener named api",
+        ));
+    }
+
+    match (admin_enabled, admin_listener.is_some()) {
+        (true, false) => Err(String::from(
+            "Socket activation passed no listener for [admin], enable auditor-server-admin.socket",
+        )),
+        (false, true) => {
+            warn!("Ignoring the socket activated admin listener, [admin] is not configured");
+            Ok((listener, None))
+        }
+        _ => Ok((listener, admin_listener)),
     }
 }
 
@@ -46,38 +320,258 @@ pub async fn run(
     de: De,
     info: Info,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let server_config: ServerConfig = server_config::parse(config.etc_dir());
-    let mut db: Db = Db::new(config.data_dir().to_string());
-    db.restore();
+    let server_config: ServerConfig = server_config::parse(&config)?;
+
+    common_log::configure(&config, server_config.log())?;
+
+    let (listener, admin_listener): (Option<TcpListener>, Option<TcpListener>) =
+        get_listeners(common_systemd::listeners(), server_config.admin().is_some())?;
+    let audit_log: AuditLog = server_audit::open(&config, &de)?;
+    let mut db: Db = Db::new(config.data_dir().to_string(), de.clone());
+    db.restore()?;
+
+    if !db_chain::check_signature(db.chain(), &de.public_key_string()) {
+        warn!("Database chain head signature is not valid, run integrity-check");
+    }
+
+    audit_log.record(
+        "database.restored",
+        serde_json::json!({
+            "head": db.chain().head(),
+            "hosts": db.iter().count(),
+            "sequence": db.chain().sequence(),
+            "signature": db.chain().signature(),
+        }),
+    );
+    audit_log.sync_enrollment(&server_config);
     let database: Arc<Mutex<Db>> = Arc::new(Mutex::new(db));
+    let server_metrics: Arc<ServerMetrics> = Arc::new(ServerMetrics::new());
+    let metrics_enabled: bool = server_config.admin().is_none();
     let address: SocketAddr = (
         Ipv4Addr::from_str(server_config.address())?,
         *server_config.port(),
     )
         .into();
-    let service = make_service_fn(|_| {
-        let config: Config = config.clone();
+    let admin_config: Option<ServerAdminConfig> = server_config.admin().clone();
+    let server_config: Arc<RwLock<ServerConfig>> = Arc::new(RwLock::new(server_config));
+    let webhooks: Webhooks = server_webhook::get(&config, server_config.clone());
+
+    tokio::spawn(server_webhook::run(
+        de.clone(),
+        info.clone(),
+        webhooks.clone(),
+    ));
+
+    tokio::spawn(reload(
+        audit_log.clone(),
+        config.clone(),
+        server_config.clone(),
+    ));
+
+    tokio::spawn(server_anchor::run(
+        config.clone(),
+        database.clone(),
+        de.clone(),
+        server_config.clone(),
+    ));
+
+    tokio::spawn(server_liveness::run(
+        config.clone(),
+        database.clone(),
+        server_config.clone(),
+        webhooks.clone(),
+    ));
+
+    if let Some(admin_config) = admin_config {
+        let admin_address: SocketAddr = (
+            Ipv4Addr::from_str(admin_config.address())?,
+            *admin_config.port(),
+        )
+            .into();
         let database: Arc<Mutex<Db>> = database.clone();
-        let de: De = de.clone();
-        let info: Info = info.clone();
+        let server_metrics: Arc<ServerMetrics> = server_metrics.clone();
+        let admin_service = make_service_fn(move |_| {
+            let database: Arc<Mutex<Db>> = database.clone();
+            let server_metrics: Arc<ServerMetrics> = server_metrics.clone();
+
+            async {
+                Ok::<_, hyper::Error>(service_fn(move |req| {
+                    handle_admin(database.to_owned(), req, server_metrics.to_owned())
+                }))
+            }
+        });
+        let admin_server = match admin_listener {
+            Some(admin_listener) => Server::from_tcp(admin_listener)?,
+            None => Server::try_bind(&admin_address)?,
+        }
+        .serve(admin_service);
+
+        info!("Admin listening on {}", admin_server.local_addr());
+
+        tokio::spawn(admin_server);
+    }
 
-        async {
+    let context: ServerContext = ServerContext::new(
+        audit_log,
+        config,
+        database.clone(),
+        de,
+        info,
+        server_config.clone(),
+        webhooks,
+    );
+    let heartbeat: Arc<AtomicU64> = Arc::new(AtomicU64::new(0));
+    let service = make_service_fn(|_| {
+        let context: ServerContext = context.clone();
+        let server_metrics: Arc<ServerMetrics> = server_metrics.clone();
+
+        heartbeat.fetch_add(1, Ordering::SeqCst);
+
+        async move {
             Ok::<_, hyper::Error>(service_fn(move |req| {
                 handle(
-                    config.to_owned(),
-                    database.to_owned(),
-                    de.to_owned(),
-                    info.to_owned(),
+                    context.to_owned(),
+                    metrics_enabled,
                     req,
+                    server_metrics.to_owned(),
                 )
             }))
         }
     });
-    let server = Server::bind(&address).serve(service);
+    let mut shutdown: Shutdown = Shutdown::new()?;
+    let (shutdown_sender, shutdown_receiver): (oneshot::Sender<()>, oneshot::Receiver<()>) =
+        oneshot::channel();
+    let server = match listener {
+        Some(listener) => Server::from_tcp(listener)?,
+        None => Server::try_bind(&address)?,
+    }
+    .serve(service);
+
+    let local_address: SocketAddr = server.local_addr();
+
+    info!("Listening on {}", local_address);
+
+    let server = server.with_graceful_shutdown(async {
+        shutdown_receiver.await.ok();
+    });
+
+    if common_systemd::is_enabled() {
+        tokio::spawn(notify_systemd(
+            local_address,
+            database.clone(),
+            heartbeat.clone(),
+        ));
+    }
+
+    tokio::pin!(server);
+    tokio::select! {
+        result = &mut server => result?,
+        signal = shutdown.recv() => {
+            let shutdown_timeout: Duration =
+                Duration::from_secs(*server_config.read().unwrap().shutdown_timeout());
+
+            info!("Received {}, draining in-flight requests", signal);
+            common_systemd::notify("STOPPING=1\nSTATUS=Draining in-flight requests");
+            shutdown_sender.send(()).ok();
+
+            match tokio::time::timeout(shutdown_timeout, &mut server).await {
+                Ok(result) => result?,
+                Err(_) => warn!(
+                    "In-flight requests did not finish within {} seconds",
+                    shutdown_timeout.as_secs()
+                ),
+            }
+        }
+    }
 
-    info!("Listening on {}", address);
+    let chain: DbChain = {
+        let mut database = database.lock().unwrap();
+        database.save();
+        database.chain().clone()
+    };
 
-    server.await?;
+    context.audit_log().record(
+        "database.saved",
+        serde_json::json!({
+            "head": chain.head(),
+            "sequence": chain.sequence(),
+            "signature": chain.signature(),
+        }),
+    );
+    info!("Database saved, shutting down");
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+    use rsa::{RsaPrivateKey, RsaPublicKey};
+
+    fn get_database() -> Arc<Mutex<Db>> {
+        let private_key: RsaPrivateKey = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let public_key: RsaPublicKey = RsaPublicKey::from(&private_key);
+
+        Arc::new(Mutex::new(Db::new(
+            String::new(),
+            De::new(None, private_key, public_key),
+        )))
+    }
+
+    #[tokio::test]
+    async fn check_progress_fails_when_listener_does_not_accept() {
+        let listener: TcpListener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let heartbeat: AtomicU64 = AtomicU64::new(0);
+
+        assert_eq!(
+            check_progress(
+                listener.local_addr().unwrap(),
+                &get_database(),
+                &heartbeat,
+                Duration::from_millis(300),
+            )
+            .await,
+            Err(String::from("The listener did not accept a connection"))
+        );
+    }
+
+    #[tokio::test]
+    async fn check_progress_requires_accepted_connection_and_database_lock() {
+        let listener: tokio::net::TcpListener =
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address: SocketAddr = listener.local_addr().unwrap();
+        let heartbeat: Arc<AtomicU64> = Arc::new(AtomicU64::new(0));
+        let accepted: Arc<AtomicU64> = heartbeat.clone();
+        tokio::spawn(async move {
+            while listener.accept().await.is_ok() {
+                accepted.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        let database: Arc<Mutex<Db>> = get_database();
+        let timeout: Duration = Duration::from_millis(300);
+
+        let holder: std::thread::JoinHandle<()> = {
+            let database: Arc<Mutex<Db>> = database.clone();
+            std::thread::spawn(move || {
+                let _database = database.lock().unwrap();
+                std::thread::sleep(Duration::from_millis(600));
+            })
+        };
+        while database.try_lock().is_ok() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let locked: Result<usize, String> =
+            check_progress(address, &database, &heartbeat, timeout).await;
+        holder.join().unwrap();
+
+        assert_eq!(
+            locked,
+            Err(String::from("The database lock could not be taken"))
+        );
+        assert_eq!(
+            check_progress(address, &database, &heartbeat, timeout).await,
+            Ok(0)
+        );
+    }
+}
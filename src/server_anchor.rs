@@ -0,0 +1,628 @@
+use crate::common_certificate;
+use crate::common_certificate::Certificate;
+use crate::common_file;
+use crate::config::Config;
+use crate::db::Db;
+use crate::db_anchor::DbAnchor;
+use crate::db_chain::DbChain;
+use crate::db_epoch::DbEpoch;
+use crate::de;
+use crate::de::De;
+use crate::server_config::{ServerAnchorSinkConfig, ServerConfig};
+use crate::server_proof;
+use hyper::{Body, Client, Method, Request};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fs;
+use std::fs::OpenOptions;
+use std::future::Future;
+use std::io::Write;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+
+const RETRY_DELAY_MAX: u64 = 86400;
+
+pub type AnchorFuture<'a> = Pin<Box<dyn Future<Output = Result<String, String>> + Send + 'a>>;
+
+pub trait AnchorSink: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn submit<'a>(&'a self, certificate: &'a Certificate) -> AnchorFuture<'a>;
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnchorRoot {
+    chain: DbChain,
+    epoch: u64,
+    from: u64,
+    host_id: String,
+    merkle_root: String,
+    proof_of_computation: f64,
+    to: u64,
+}
+
+impl AnchorRoot {
+    pub fn new(
+        chain: DbChain,
+        epoch: u64,
+        from: u64,
+        host_id: String,
+        merkle_root: String,
+        proof_of_computation: f64,
+        to: u64,
+    ) -> Self {
+        Self {
+            chain,
+            epoch,
+            from,
+            host_id,
+            merkle_root,
+            proof_of_computation,
+            to,
+        }
+    }
+}
+
+pub struct FileAnchorSink {
+    name: String,
+    path: String,
+}
+
+impl FileAnchorSink {
+    pub fn new(name: String, path: String) -> Self {
+        Self { name, path }
+    }
+}
+
+impl AnchorSink for FileAnchorSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn submit<'a>(&'a self, certificate: &'a Certificate) -> AnchorFuture<'a> {
+        Box::pin(async move {
+            let line: String = serde_json::to_string(certificate).map_err(|x| x.to_string())?;
+            let mut file = OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(&self.path)
+                .map_err(|x| x.to_string())?;
+
+            writeln!(file, "{}", line).map_err(|x| x.to_string())?;
+
+            Ok(de::digest(&line))
+        })
+    }
+}
+
+pub struct JsonRpcAnchorSink {
+    method: String,
+    name: String,
+    url: String,
+}
+
+impl JsonRpcAnchorSink {
+    pub fn new(method: String, name: String, url: String) -> Self {
+        Self { method, name, url }
+    }
+}
+
+impl AnchorSink for JsonRpcAnchorSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn submit<'a>(&'a self, certificate: &'a Certificate) -> AnchorFuture<'a> {
+        Box::pin(async move {
+            let body: String = serde_json::json!({
+                "id": 1,
+                "jsonrpc": "2.0",
+                "method": self.method,
+                "params": [certificate],
+            })
+            .to_string();
+            let request = Request::builder()
+                .method(Method::POST)
+                .uri(&self.url)
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .map_err(|x| x.to_string())?;
+            let response = Client::new()
+                .request(request)
+                .await
+                .map_err(|x| x.to_string())?;
+
+            if !response.status().is_success() {
+                return Err(format!("HTTP status {}", response.status()));
+            }
+
+            let body_bytes = hyper::body::to_bytes(response.into_body())
+                .await
+                .map_err(|x| x.to_string())?;
+            let value: Value = serde_json::from_slice(&body_bytes).map_err(|x| x.to_string())?;
+
+            match (value.get("result"), value.get("error")) {
+                (_, Some(error)) if !error.is_null() => Err(error.to_string()),
+                (Some(Value::String(receipt)), _) => Ok(receipt.to_string()),
+                (Some(result), _) if !result.is_null() => Ok(result.to_string()),
+                _ => Err(String::from("Missing result")),
+            }
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct AnchorRetry {
+    attempts: u32,
+    epoch: u64,
+    host_id: String,
+    next_attempt: u64,
+    sink: String,
+}
+
+impl AnchorRetry {
+    fn is_for(&self, sink: &str, host_id: &str, epoch: u64) -> bool {
+        self.sink == sink && self.host_id == host_id && self.epoch == epoch
+    }
+}
+
+pub fn get(server_config: &ServerConfig) -> Vec<Box<dyn AnchorSink>> {
+    server_config
+        .anchor()
+        .sinks()
+        .iter()
+        .map(|sink_config| -> Box<dyn AnchorSink> {
+            match sink_config {
+                ServerAnchorSinkConfig::File { name, path } => Box::new(FileAnchorSink::new(
+                    name.clone().unwrap_or_else(|| String::from("file")),
+                    path.to_string(),
+                )),
+                ServerAnchorSinkConfig::JsonRpc { method, name, url } => {
+                    Box::new(JsonRpcAnchorSink::new(
+                        method.to_string(),
+                        name.clone().unwrap_or_else(|| String::from("json-rpc")),
+                        url.to_string(),
+                    ))
+                }
+            }
+        })
+        .collect()
+}
+
+pub async fn run(
+    config: Config,
+    database: Arc<Mutex<Db>>,
+    de: De,
+    server_config: Arc<RwLock<ServerConfig>>,
+) {
+    let sinks: Vec<Box<dyn AnchorSink>> = get(&server_config.read().unwrap());
+
+    if sinks.is_empty() {
+        return;
+    }
+
+    let retries_path: String = format!(
+        "{dir}{file}",
+        dir = config.data_dir(),
+        file = "anchor_retries.json"
+    );
+    let mut retries: Vec<AnchorRetry> = restore(&retries_path);
+    retries.retain(|retry| sinks.iter().any(|sink| sink.name() == retry.sink));
+
+    loop {
+        let server_config: ServerConfig = server_config.read().unwrap().clone();
+        let interval: u64 = (*server_config.anchor().interval()).max(1);
+
+        if anchor(&database, &de, &server_config, &sinks, &mut retries).await {
+            save(&retries_path, &retries);
+        }
+
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+    }
+}
+
+async fn anchor(
+    database: &Arc<Mutex<Db>>,
+    de: &De,
+    server_config: &ServerConfig,
+    sinks: &[Box<dyn AnchorSink>],
+    retries: &mut Vec<AnchorRetry>,
+) -> bool {
+    let now: u64 = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let mut pending: Vec<(String, u64, AnchorRoot, Vec<usize>)> = vec![];
+
+    {
+        let mut database = database.lock().unwrap();
+
+        freeze_closed_epochs(&mut database, server_config, now);
+
+        for (host_id, db_record) in database.iter() {
+            for db_epoch in db_record.epochs() {
+                let indexes: Vec<usize> = sinks
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, sink)| {
+                        !db_epoch
+                            .anchors()
+                            .iter()
+                            .any(|db_anchor| db_anchor.sink() == sink.name())
+                            && retries
+                                .iter()
+                                .find(|retry| retry.is_for(sink.name(), host_id, *db_epoch.epoch()))
+                                .is_none_or(|retry| retry.next_attempt <= now)
+                    })
+                    .map(|(index, _)| index)
+                    .collect();
+
+                if !indexes.is_empty() {
+                    pending.push((
+                        host_id.to_string(),
+                        *db_epoch.epoch(),
+                        get_anchor_root(database.chain(), host_id, db_epoch),
+                        indexes,
+                    ));
+                }
+            }
+        }
+    }
+
+    let mut changed: bool = false;
+    let mut receipts: Vec<(String, u64, DbAnchor)> = vec![];
+
+    for (host_id, epoch, anchor_root, indexes) in pending {
+        let certificate: Certificate =
+            common_certificate::issue(de, serde_json::to_string(&anchor_root).unwrap());
+
+        for index in indexes {
+            let sink: &dyn AnchorSink = sinks[index].as_ref();
+
+            match sink.submit(&certificate).await {
+                Ok(receipt) => {
+                    info!(
+                        "Anchored epoch {} of host = {} to {} with receipt = {}",
+                        epoch,
+                        host_id,
+                        sink.name(),
+                        receipt
+                    );
+                    let retry_count: usize = retries.len();
+                    retries.retain(|retry| !retry.is_for(sink.name(), &host_id, epoch));
+                    changed |= retries.len() != retry_count;
+                    receipts.push((
+                        host_id.to_string(),
+                        epoch,
+                        DbAnchor::new(receipt, sink.name().to_string(), now),
+                    ));
+                }
+                Err(x) => {
+                    let position: usize = match retries
+                        .iter()
+                        .position(|retry| retry.is_for(sink.name(), &host_id, epoch))
+                    {
+                        Some(position) => position,
+                        None => {
+                            retries.push(AnchorRetry {
+                                attempts: 0,
+                                epoch,
+                                host_id: host_id.to_string(),
+                                next_attempt: 0,
+                                sink: sink.name().to_string(),
+                            });
+                            retries.len() - 1
+                        }
+                    };
+                    let retry: &mut AnchorRetry = &mut retries[position];
+                    let delay: u64 = server_config
+                        .anchor()
+                        .interval()
+                        .saturating_mul(1 << retry.attempts.min(16))
+                        .min(RETRY_DELAY_MAX);
+                    retry.attempts += 1;
+                    retry.next_attempt = now + delay;
+                    changed = true;
+
+                    warn!(
+                        "Anchoring epoch {} of host = {} to {} failed (attempt {}): {}",
+                        epoch,
+                        host_id,
+                        sink.name(),
+                        retry.attempts,
+                        x
+                    );
+                }
+            }
+        }
+    }
+
+    if receipts.is_empty() {
+        return changed;
+    }
+
+    let mut database = database.lock().unwrap();
+
+    for (host_id, epoch, db_anchor) in receipts {
+        if let Some(db_epoch) = database.get_mut(&host_id).and_then(|db_record| {
+            db_record
+                .epochs_mut()
+                .iter_mut()
+                .find(|db_epoch| *db_epoch.epoch() == epoch)
+        }) {
+            db_epoch.anchors_mut().push(db_anchor);
+        }
+    }
+
+    database.save();
+
+    changed
+}
+
+fn freeze_closed_epochs(database: &mut Db, server_config: &ServerConfig, now: u64) {
+    let epoch_length: u64 = (*server_config.epoch_length()).max(1);
+    let current_epoch: u64 = now / epoch_length;
+    let mut frozen: bool = false;
+
+    for (host_id, db_record) in database.iter_mut() {
+        let epochs: BTreeSet<u64> = db_record
+            .statistics()
+            .iter()
+            .map(|statistic| statistic.timestamp() / epoch_length)
+            .filter(|epoch| *epoch < current_epoch)
+            .filter(|epoch| {
+                !db_record
+                    .epochs()
+                    .iter()
+                    .any(|db_epoch| db_epoch.epoch() == epoch)
+            })
+            .collect();
+
+        for epoch in epochs {
+            server_proof::freeze(
+                db_record,
+                host_id,
+                epoch,
+                epoch * epoch_length,
+                (epoch + 1) * epoch_length,
+                server_config,
+                now,
+            );
+            frozen = true;
+        }
+    }
+
+    if frozen {
+        database.save();
+    }
+}
+
+fn get_anchor_root(chain: &DbChain, host_id: &str, db_epoch: &DbEpoch) -> AnchorRoot {
+    AnchorRoot::new(
+        chain.clone(),
+        *db_epoch.epoch(),
+        *db_epoch.from(),
+        host_id.to_string(),
+        db_epoch.merkle_root().to_string(),
+        *db_epoch.proof_of_computation(),
+        *db_epoch.to(),
+    )
+}
+
+fn restore(retries_path: &str) -> Vec<AnchorRetry> {
+    if !Path::new(retries_path).exists() {
+        return vec![];
+    }
+
+    match fs::read_to_string(retries_path)
+        .map_err(|x| x.to_string())
+        .and_then(|contents| serde_json::from_str(&contents).map_err(|x| x.to_string()))
+    {
+        Ok(retries) => retries,
+        Err(x) => {
+            warn!("Can not restore anchor retries: {}", x);
+            vec![]
+        }
+    }
+}
+
+fn save(retries_path: &str, retries: &[AnchorRetry]) {
+    if let Err(x) = common_file::write_atomic(retries_path, &serde_json::to_vec(retries).unwrap()) {
+        warn!("Can not save anchor retries: {}", x);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common_config::ConfigSources;
+    use crate::mode::Mode;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Response, Server, StatusCode};
+    use rand::rngs::OsRng;
+    use rsa::{RsaPrivateKey, RsaPublicKey};
+    use serde_json::json;
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+
+    fn get_de() -> De {
+        let private_key: RsaPrivateKey = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let public_key: RsaPublicKey = RsaPublicKey::from(&private_key);
+
+        De::new(None, private_key, public_key)
+    }
+
+    fn get_endpoint(status: StatusCode, body: Value) -> String {
+        let service = make_service_fn(move |_| {
+            let body: Value = body.clone();
+
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                    let body: Value = body.clone();
+
+                    async move {
+                        let request: Value = serde_json::from_slice(
+                            &hyper::body::to_bytes(request.into_body()).await.unwrap(),
+                        )
+                        .unwrap();
+                        assert_eq!(request["method"], "anchor_submit");
+
+                        Ok::<_, Infallible>(
+                            Response::builder()
+                                .status(status)
+                                .body(Body::from(body.to_string()))
+                                .unwrap(),
+                        )
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(service);
+        let url: String = format!("http://{}/", server.local_addr());
+        tokio::spawn(server);
+
+        url
+    }
+
+    fn get_data_dir(name: &str) -> String {
+        let data_dir: String = format!(
+            "{}/auditor_anchor_{}_{}/",
+            std::env::temp_dir().display(),
+            std::process::id(),
+            name
+        );
+        fs::create_dir_all(&data_dir).unwrap();
+
+        data_dir
+    }
+
+    fn get_database(data_dir: &str, de: &De) -> Arc<Mutex<Db>> {
+        let mut db: Db = Db::new(data_dir.to_string(), de.clone());
+        db.insert(
+            String::from("host"),
+            serde_json::from_value(json!({
+                "epochs": [],
+                "public_key": "",
+                "statistics": [{
+                    "blockchain_hash": "",
+                    "cpu_usage": 50.0,
+                    "id": "1",
+                    "mt_2_result": 1.0,
+                    "mt_4_result": 1.0,
+                    "mt_8_result": 1.0,
+                    "st_result": 1.0,
+                    "timestamp": 1,
+                    "used_for_proof": false,
+                }],
+            }))
+            .unwrap(),
+        );
+
+        Arc::new(Mutex::new(db))
+    }
+
+    fn get_server_config(url: &str) -> ServerConfig {
+        serde_json::from_value(json!({
+            "address": "127.0.0.1",
+            "anchor": {
+                "interval": 1,
+                "sinks": [{ "method": "anchor_submit", "type": "json-rpc", "url": url }],
+            },
+            "epoch_length": 10,
+            "port": 8081,
+        }))
+        .unwrap()
+    }
+
+    async fn run_until(
+        data_dir: &str,
+        database: &Arc<Mutex<Db>>,
+        de: &De,
+        url: &str,
+        done: impl Fn() -> bool,
+    ) {
+        let config: Config = Config::new(
+            data_dir.to_string(),
+            String::new(),
+            String::from("server"),
+            Mode::Server,
+            vec![],
+            ConfigSources::new(),
+        );
+        let task = tokio::spawn(run(
+            config,
+            database.clone(),
+            de.clone(),
+            Arc::new(RwLock::new(get_server_config(url))),
+        ));
+
+        for _ in 0..100 {
+            if done() {
+                break;
+            }
+
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn json_rpc_receipt_is_stored_in_epoch() {
+        let de: De = get_de();
+        let data_dir: String = get_data_dir("receipt");
+        let database: Arc<Mutex<Db>> = get_database(&data_dir, &de);
+        let url: String = get_endpoint(
+            StatusCode::OK,
+            json!({ "id": 1, "jsonrpc": "2.0", "result": "0xreceipt" }),
+        );
+        let anchors = || -> Vec<DbAnchor> {
+            database
+                .lock()
+                .unwrap()
+                .get("host")
+                .and_then(|db_record| db_record.epochs().first())
+                .map(|db_epoch| db_epoch.anchors().to_vec())
+                .unwrap_or_default()
+        };
+
+        run_until(&data_dir, &database, &de, &url, || !anchors().is_empty()).await;
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        let anchors: Vec<DbAnchor> = anchors();
+        assert_eq!(anchors.len(), 1);
+        let anchor: Value = serde_json::to_value(&anchors[0]).unwrap();
+        assert_eq!(anchor["receipt"], "0xreceipt");
+        assert_eq!(anchor["sink"], "json-rpc");
+    }
+
+    #[tokio::test]
+    async fn json_rpc_failure_is_saved_for_retry() {
+        let de: De = get_de();
+        let data_dir: String = get_data_dir("failure");
+        let database: Arc<Mutex<Db>> = get_database(&data_dir, &de);
+        let url: String = get_endpoint(StatusCode::INTERNAL_SERVER_ERROR, json!({}));
+        let retries_path: String = format!("{}anchor_retries.json", data_dir);
+
+        run_until(&data_dir, &database, &de, &url, || {
+            Path::new(&retries_path).exists()
+        })
+        .await;
+        let retries: Vec<AnchorRetry> = restore(&retries_path);
+        let unanchored: bool = database.lock().unwrap().get("host").unwrap().epochs()[0]
+            .anchors()
+            .is_empty();
+        fs::remove_dir_all(&data_dir).unwrap();
+
+        assert!(unanchored);
+        assert_eq!(retries.len(), 1);
+        assert_eq!(retries[0].attempts, 1);
+        assert_eq!(retries[0].epoch, 0);
+        assert_eq!(retries[0].host_id, "host");
+        assert_eq!(retries[0].sink, "json-rpc");
+    }
+}
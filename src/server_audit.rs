@@ -0,0 +1,428 @@
+use crate::config::Config;
+use crate::de;
+use crate::de::De;
+use crate::server_config::ServerConfig;
+use clap::ArgMatches;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AuditEntry {
+    data: Value,
+    event: String,
+    hash: String,
+    issued_at: u64,
+    previous_hash: String,
+    sequence: u64,
+    signature: String,
+}
+
+impl AuditEntry {
+    fn host_id(&self) -> Option<&str> {
+        self.data
+            .get("host_id")
+            .and_then(|host_id| host_id.as_str())
+    }
+}
+
+#[derive(Debug)]
+struct AuditState {
+    approved_hosts: HashSet<String>,
+    head: String,
+    path: String,
+    revoked_hosts: HashSet<String>,
+    sequence: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct AuditLog {
+    de: De,
+    state: Arc<Mutex<AuditState>>,
+}
+
+impl AuditLog {
+    pub fn record(&self, event: &str, data: Value) {
+        let mut state = self.state.lock().unwrap();
+        let issued_at: u64 = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let sequence: u64 = state.sequence + 1;
+        let hash: String = hash_entry(&state.head, sequence, issued_at, event, &data);
+        let audit_entry: AuditEntry = AuditEntry {
+            data,
+            event: event.to_string(),
+            hash: hash.to_string(),
+            issued_at,
+            previous_hash: state.head.to_string(),
+            sequence,
+            signature: self.de.sign(&hash),
+        };
+        let line: String = serde_json::to_string(&audit_entry).unwrap();
+        let result: std::io::Result<()> = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&state.path)
+            .and_then(|mut file| {
+                writeln!(file, "{}", line)?;
+                file.sync_data()
+            });
+
+        match result {
+            Ok(()) => {
+                state.head = hash;
+                state.sequence = sequence;
+                apply(&mut state, &audit_entry);
+            }
+            Err(x) => warn!("Can not write audit log entry = {}: {}", event, x),
+        }
+    }
+
+    pub fn sync_enrollment(&self, server_config: &ServerConfig) {
+        let (approved_hosts, revoked_hosts): (HashSet<String>, HashSet<String>) = {
+            let state = self.state.lock().unwrap();
+            (state.approved_hosts.clone(), state.revoked_hosts.clone())
+        };
+        let enrollment = server_config.enrollment();
+
+        for host_id in enrollment.approved_hosts() {
+            if !approved_hosts.contains(host_id) {
+                self.record("host.approved", serde_json::json!({ "host_id": host_id }));
+            }
+        }
+
+        for host_id in enrollment.revoked_hosts() {
+            if !revoked_hosts.contains(host_id) {
+                self.record("host.revoked", serde_json::json!({ "host_id": host_id }));
+            }
+        }
+
+        for host_id in &revoked_hosts {
+            if !enrollment.revoked_hosts().contains(host_id) {
+                self.record("host.reinstated", serde_json::json!({ "host_id": host_id }));
+            }
+        }
+    }
+}
+
+fn apply(state: &mut AuditState, audit_entry: &AuditEntry) {
+    if let Some(host_id) = audit_entry.host_id() {
+        match audit_entry.event.as_str() {
+            "host.approved" => {
+                state.approved_hosts.insert(host_id.to_string());
+            }
+            "host.reinstated" => {
+                state.revoked_hosts.remove(host_id);
+            }
+            "host.revoked" => {
+                state.revoked_hosts.insert(host_id.to_string());
+            }
+            _ => {}
+        }
+    }
+}
+
+fn get_path(config: &Config) -> String {
+    format!("{dir}{file}", dir = config.data_dir(), file = "audit.log")
+}
+
+fn hash_entry(
+    previous_hash: &str,
+    sequence: u64,
+    issued_at: u64,
+    event: &str,
+    data: &Value,
+) -> String {
+    de::digest(&format!(
+        "{}.{}.{}.{}.{}",
+        previous_hash, sequence, issued_at, event, data
+    ))
+}
+
+fn read(path: &str) -> Result<Vec<AuditEntry>, String> {
+    if !Path::new(path).exists() {
+        return Ok(vec![]);
+    }
+
+    fs::read_to_string(path)
+        .map_err(|x| format!("Can not read audit log {}: {}", path, x))?
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line)
+                .map_err(|x| format!("Audit log line {} is not valid: {}", index + 1, x))
+        })
+        .collect()
+}
+
+pub fn check(audit_entries: &[AuditEntry], public_key: &str) -> Vec<String> {
+    let mut errors: Vec<String> = vec![];
+    let mut head: String = String::new();
+
+    for (index, audit_entry) in audit_entries.iter().enumerate() {
+        let sequence: u64 = index as u64 + 1;
+
+        if audit_entry.sequence != sequence {
+            errors.push(format!(
+                "Sequence {} found where {} was expected",
+                audit_entry.sequence, sequence
+            ));
+            break;
+        }
+
+        if audit_entry.previous_hash != head {
+            errors.push(format!(
+                "Entry {} does not link to the previous entry",
+                sequence
+            ));
+        }
+
+        let hash: String = hash_entry(
+            &audit_entry.previous_hash,
+            audit_entry.sequence,
+            audit_entry.issued_at,
+            &audit_entry.event,
+            &audit_entry.data,
+        );
+
+        if hash != audit_entry.hash {
+            errors.push(format!("Entry {} has been modified", sequence));
+        }
+
+        if !de::check_sign(&audit_entry.hash, public_key, &audit_entry.signature) {
+            errors.push(format!("Entry {} has an invalid signature", sequence));
+        }
+
+        head = audit_entry.hash.to_string();
+    }
+
+    errors
+}
+
+pub fn open(config: &Config, de: &De) -> Result<AuditLog, String> {
+    let path: String = get_path(config);
+    let audit_entries: Vec<AuditEntry> = read(&path)?;
+    let mut state: AuditState = AuditState {
+        approved_hosts: HashSet::new(),
+        head: String::new(),
+        path,
+        revoked_hosts: HashSet::new(),
+        sequence: 0,
+    };
+
+    for audit_entry in &audit_entries {
+        apply(&mut state, audit_entry);
+    }
+
+    if let Some(audit_entry) = audit_entries.last() {
+        state.head = audit_entry.hash.to_string();
+        state.sequence = audit_entry.sequence;
+    }
+
+    Ok(AuditLog {
+        de: de.clone(),
+        state: Arc::new(Mutex::new(state)),
+    })
+}
+
+pub fn run(
+    config: Config,
+    de: De,
+    audit_parameters: &ArgMatches,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let audit_entries: Vec<AuditEntry> = read(&get_path(&config))?;
+
+    if audit_parameters.is_present("verify") {
+        let errors: Vec<String> = check(&audit_entries, &de.public_key_string());
+
+        for error in &errors {
+            println!("{}", error);
+        }
+
+        if !errors.is_empty() {
+            return Err(
+                format!("Audit log verification failed with {} errors", errors.len()).into(),
+            );
+        }
+
+        println!("Audit log is valid");
+        println!("entries = {}", audit_entries.len());
+        println!(
+            "head = {}",
+            audit_entries
+                .last()
+                .map(|audit_entry| audit_entry.hash.as_str())
+                .unwrap_or_default()
+        );
+
+        return Ok(());
+    }
+
+    let parameter = |name: &str| -> Result<Option<u64>, std::num::ParseIntError> {
+        audit_parameters
+            .value_of(name)
+            .map(|value| value.parse())
+            .transpose()
+    };
+    let from: Option<u64> = parameter("from")?;
+    let to: Option<u64> = parameter("to")?;
+
+    for audit_entry in audit_entries.iter().filter(|audit_entry| {
+        audit_parameters
+            .value_of("event")
+            .is_none_or(|event| audit_entry.event == event)
+            && audit_parameters
+                .value_of("host")
+                .is_none_or(|host_id| audit_entry.host_id() == Some(host_id))
+            && from.is_none_or(|from| audit_entry.issued_at >= from)
+            && to.is_none_or(|to| audit_entry.issued_at < to)
+    }) {
+        println!("{}", serde_json::to_string(audit_entry)?);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+    use rsa::{RsaPrivateKey, RsaPublicKey};
+
+    fn get_de() -> De {
+        let private_key: RsaPrivateKey = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let public_key: RsaPublicKey = RsaPublicKey::from(&private_key);
+
+        De::new(None, private_key, public_key)
+    }
+
+    fn get_audit_log(de: &De, name: &str) -> AuditLog {
+        let path: String = format!(
+            "{}/auditor_audit_{}_{}.log",
+            std::env::temp_dir().display(),
+            std::process::id(),
+            name
+        );
+
+        AuditLog {
+            de: de.clone(),
+            state: Arc::new(Mutex::new(AuditState {
+                approved_hosts: HashSet::new(),
+                head: String::new(),
+                path,
+                revoked_hosts: HashSet::new(),
+                sequence: 0,
+            })),
+        }
+    }
+
+    fn get_audit_entries(de: &De, name: &str) -> Vec<AuditEntry> {
+        let audit_log: AuditLog = get_audit_log(de, name);
+
+        for host_id in ["a", "b", "c"] {
+            audit_log.record("host.first_seen", serde_json::json!({ "host_id": host_id }));
+        }
+
+        let path: String = audit_log.state.lock().unwrap().path.to_string();
+        let audit_entries: Vec<AuditEntry> = read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        audit_entries
+    }
+
+    fn get_server_config(approved_hosts: &[&str], revoked_hosts: &[&str]) -> ServerConfig {
+        serde_json::from_value(serde_json::json!({
+            "address": "127.0.0.1",
+            "enrollment": {
+                "approval_required": true,
+                "approved_hosts": approved_hosts,
+                "revoked_hosts": revoked_hosts,
+            },
+            "port": 8081,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn check_accepts_valid_log() {
+        let de: De = get_de();
+        let audit_entries: Vec<AuditEntry> = get_audit_entries(&de, "valid");
+
+        assert_eq!(audit_entries.len(), 3);
+        assert!(check(&audit_entries, &de.public_key_string()).is_empty());
+    }
+
+    #[test]
+    fn check_detects_modified_entry() {
+        let de: De = get_de();
+        let mut audit_entries: Vec<AuditEntry> = get_audit_entries(&de, "modified");
+        audit_entries[1].data = serde_json::json!({ "host_id": "d" });
+
+        assert_eq!(
+            check(&audit_entries, &de.public_key_string()),
+            vec![String::from("Entry 2 has been modified")]
+        );
+    }
+
+    #[test]
+    fn check_detects_removed_entry() {
+        let de: De = get_de();
+        let mut audit_entries: Vec<AuditEntry> = get_audit_entries(&de, "removed");
+        audit_entries.remove(1);
+
+        assert_eq!(
+            check(&audit_entries, &de.public_key_string()),
+            vec![String::from("Sequence 3 found where 2 was expected")]
+        );
+    }
+
+    #[test]
+    fn check_detects_entries_signed_by_other_key() {
+        let audit_entries: Vec<AuditEntry> = get_audit_entries(&get_de(), "other_key");
+
+        assert_eq!(
+            check(&audit_entries, &get_de().public_key_string()).len(),
+            3
+        );
+    }
+
+    #[test]
+    fn sync_enrollment_records_approvals_revocations_and_reinstatements_once() {
+        let audit_log: AuditLog = get_audit_log(&get_de(), "enrollment");
+        let path: String = audit_log.state.lock().unwrap().path.to_string();
+
+        audit_log.sync_enrollment(&get_server_config(&["a"], &["b"]));
+        audit_log.sync_enrollment(&get_server_config(&["a"], &["b"]));
+        audit_log.sync_enrollment(&get_server_config(&["a"], &[]));
+
+        let events: Vec<(String, String)> = read(&path)
+            .unwrap()
+            .iter()
+            .map(|audit_entry| {
+                (
+                    audit_entry.event.to_string(),
+                    audit_entry.host_id().unwrap().to_string(),
+                )
+            })
+            .collect();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                (String::from("host.approved"), String::from("a")),
+                (String::from("host.revoked"), String::from("b")),
+                (String::from("host.reinstated"), String::from("b")),
+            ]
+        );
+    }
+}
@@ -1,10 +1,268 @@
-use serde_derive::Deserialize;
-use std::fs;
+use crate::common_config;
+use crate::common_config::ConfigSources;
+use crate::common_log::LogConfig;
+use crate::config::Config;
+use hyper::Uri;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::str::FromStr;
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ServerAdminConfig {
+    address: String,
+    port: u16,
+}
+
+impl ServerAdminConfig {
+    pub fn address(&self) -> &String {
+        &self.address
+    }
+
+    pub fn port(&self) -> &u16 {
+        &self.port
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ServerAnchorSinkConfig {
+    File {
+        #[serde(default)]
+        name: Option<String>,
+        path: String,
+    },
+    JsonRpc {
+        #[serde(default = "default_anchor_method")]
+        method: String,
+        #[serde(default)]
+        name: Option<String>,
+        url: String,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ServerAnchorConfig {
+    #[serde(default = "default_anchor_interval")]
+    interval: u64,
+    #[serde(default)]
+    sinks: Vec<ServerAnchorSinkConfig>,
+}
+
+impl Default for ServerAnchorConfig {
+    fn default() -> Self {
+        Self {
+            interval: default_anchor_interval(),
+            sinks: vec![],
+        }
+    }
+}
+
+impl ServerAnchorConfig {
+    pub fn interval(&self) -> &u64 {
+        &self.interval
+    }
+
+    pub fn sinks(&self) -> &Vec<ServerAnchorSinkConfig> {
+        &self.sinks
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServerGapPolicy {
+    Cap,
+    None,
+    Scale,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ServerLivenessConfig {
+    #[serde(default = "default_liveness_check_interval")]
+    check_interval: u64,
+    #[serde(default)]
+    expected_intervals: HashMap<String, u64>,
+    #[serde(default = "default_gap_policy")]
+    gap_policy: ServerGapPolicy,
+    #[serde(default = "default_liveness_late_after")]
+    late_after: u64,
+    #[serde(default = "default_liveness_missing_after")]
+    missing_after: u64,
+}
+
+impl Default for ServerLivenessConfig {
+    fn default() -> Self {
+        Self {
+            check_interval: default_liveness_check_interval(),
+            expected_intervals: HashMap::new(),
+            gap_policy: default_gap_policy(),
+            late_after: default_liveness_late_after(),
+            missing_after: default_liveness_missing_after(),
+        }
+    }
+}
+
+impl ServerLivenessConfig {
+    pub fn check_interval(&self) -> &u64 {
+        &self.check_interval
+    }
+
+    pub fn expected_intervals(&self) -> &HashMap<String, u64> {
+        &self.expected_intervals
+    }
+
+    pub fn gap_policy(&self) -> &ServerGapPolicy {
+        &self.gap_policy
+    }
+
+    pub fn late_after(&self) -> &u64 {
+        &self.late_after
+    }
+
+    pub fn missing_after(&self) -> &u64 {
+        &self.missing_after
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ServerScoringConfig {
+    #[serde(default)]
+    cpu_core_balance_weight: f64,
+    #[serde(default)]
+    disk_io_weight: f64,
+    #[serde(default = "default_network_reference_throughput")]
+    network_reference_throughput: f64,
+    #[serde(default)]
+    network_weight: f64,
+    #[serde(default)]
+    workload_cpu_only: bool,
+}
+
+impl Default for ServerScoringConfig {
+    fn default() -> Self {
+        Self {
+            cpu_core_balance_weight: 0.0,
+            disk_io_weight: 0.0,
+            network_reference_throughput: default_network_reference_throughput(),
+            network_weight: 0.0,
+            workload_cpu_only: false,
+        }
+    }
+}
+
+impl ServerScoringConfig {
+    pub fn cpu_core_balance_weight(&self) -> &f64 {
+        &self.cpu_core_balance_weight
+    }
+
+    pub fn disk_io_weight(&self) -> &f64 {
+        &self.disk_io_weight
+    }
+
+    pub fn network_reference_throughput(&self) -> &f64 {
+        &self.network_reference_throughput
+    }
+
+    pub fn network_weight(&self) -> &f64 {
+        &self.network_weight
+    }
+
+    pub fn workload_cpu_only(&self) -> &bool {
+        &self.workload_cpu_only
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ServerEnrollmentConfig {
+    #[serde(default)]
+    approval_required: bool,
+    #[serde(default)]
+    approved_hosts: Vec<String>,
+    #[serde(default)]
+    revoked_hosts: Vec<String>,
+}
+
+impl ServerEnrollmentConfig {
+    pub fn approval_required(&self) -> &bool {
+        &self.approval_required
+    }
+
+    pub fn approved_hosts(&self) -> &Vec<String> {
+        &self.approved_hosts
+    }
+
+    pub fn revoked_hosts(&self) -> &Vec<String> {
+        &self.revoked_hosts
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ServerWebhooksConfig {
+    #[serde(default = "default_webhooks_max_attempts")]
+    max_attempts: u32,
+    #[serde(default = "default_webhooks_signature_failure_threshold")]
+    signature_failure_threshold: u64,
+    #[serde(default)]
+    urls: Vec<String>,
+    #[serde(default = "default_webhooks_validation_rejection_threshold")]
+    validation_rejection_threshold: u64,
+}
+
+impl Default for ServerWebhooksConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_webhooks_max_attempts(),
+            signature_failure_threshold: default_webhooks_signature_failure_threshold(),
+            urls: vec![],
+            validation_rejection_threshold: default_webhooks_validation_rejection_threshold(),
+        }
+    }
+}
+
+impl ServerWebhooksConfig {
+    pub fn max_attempts(&self) -> &u32 {
+        &self.max_attempts
+    }
+
+    pub fn signature_failure_threshold(&self) -> &u64 {
+        &self.signature_failure_threshold
+    }
+
+    pub fn urls(&self) -> &Vec<String> {
+        &self.urls
+    }
+
+    pub fn validation_rejection_threshold(&self) -> &u64 {
+        &self.validation_rejection_threshold
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ServerConfig {
     address: String,
+    #[serde(default)]
+    admin: Option<ServerAdminConfig>,
+    #[serde(default)]
+    anchor: ServerAnchorConfig,
+    #[serde(default)]
+    enrollment: ServerEnrollmentConfig,
+    #[serde(default = "default_epoch_length")]
+    epoch_length: u64,
+    #[serde(default = "default_expected_interval")]
+    expected_interval: u64,
+    #[serde(default)]
+    liveness: ServerLivenessConfig,
+    #[serde(default)]
+    log: LogConfig,
+    #[serde(default = "default_max_sample_age")]
+    max_sample_age: u64,
     port: u16,
+    #[serde(default)]
+    scoring: ServerScoringConfig,
+    #[serde(default = "default_shutdown_timeout")]
+    shutdown_timeout: u64,
+    #[serde(default)]
+    webhooks: ServerWebhooksConfig,
 }
 
 impl ServerConfig {
@@ -12,20 +270,195 @@ impl ServerConfig {
         &self.address
     }
 
+    pub fn admin(&self) -> &Option<ServerAdminConfig> {
+        &self.admin
+    }
+
+    pub fn anchor(&self) -> &ServerAnchorConfig {
+        &self.anchor
+    }
+
+    pub fn enrollment(&self) -> &ServerEnrollmentConfig {
+        &self.enrollment
+    }
+
+    pub fn epoch_length(&self) -> &u64 {
+        &self.epoch_length
+    }
+
+    pub fn expected_interval(&self) -> &u64 {
+        &self.expected_interval
+    }
+
+    pub fn liveness(&self) -> &ServerLivenessConfig {
+        &self.liveness
+    }
+
+    pub fn log(&self) -> &LogConfig {
+        &self.log
+    }
+
+    pub fn max_sample_age(&self) -> &u64 {
+        &self.max_sample_age
+    }
+
     pub fn port(&self) -> &u16 {
         &self.port
     }
+
+    pub fn scoring(&self) -> &ServerScoringConfig {
+        &self.scoring
+    }
+
+    pub fn shutdown_timeout(&self) -> &u64 {
+        &self.shutdown_timeout
+    }
+
+    pub fn webhooks(&self) -> &ServerWebhooksConfig {
+        &self.webhooks
+    }
+
+    pub fn reload(&self, server_config: ServerConfig) -> (ServerConfig, Vec<&'static str>) {
+        let mut server_config: ServerConfig = server_config;
+        let mut restart_required: Vec<&'static str> = vec![];
+
+        if server_config.address != self.address {
+            server_config.address = self.address.to_string();
+            restart_required.push("address");
+        }
+
+        if server_config.admin != self.admin {
+            server_config.admin = self.admin.clone();
+            restart_required.push("admin");
+        }
+
+        if server_config.anchor.sinks != self.anchor.sinks {
+            server_config.anchor.sinks = self.anchor.sinks.clone();
+            restart_required.push("anchor.sinks");
+        }
+
+        if server_config.epoch_length != self.epoch_length {
+            server_config.epoch_length = self.epoch_length;
+            restart_required.push("epoch_length");
+        }
+
+        if server_config.port != self.port {
+            server_config.port = self.port;
+            restart_required.push("port");
+        }
+
+        (server_config, restart_required)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        Ipv4Addr::from_str(&self.address).map_err(|x| format!("address: {}", x))?;
+
+        if let Some(admin) = &self.admin {
+            Ipv4Addr::from_str(admin.address()).map_err(|x| format!("admin.address: {}", x))?;
+        }
+
+        if self.epoch_length == 0 {
+            return Err(String::from("epoch_length: must be greater than 0"));
+        }
+
+        if self.expected_interval == 0 {
+            return Err(String::from("expected_interval: must be greater than 0"));
+        }
+
+        if self.max_sample_age == 0 {
+            return Err(String::from("max_sample_age: must be greater than 0"));
+        }
+
+        for url in self.webhooks.urls() {
+            let uri: Uri = url
+                .parse()
+                .map_err(|x| format!("webhooks.urls: {} is not valid: {}", url, x))?;
+
+            if uri.scheme_str() != Some("http") {
+                return Err(format!("webhooks.urls: {} is not an http URL", url));
+            }
+        }
+
+        self.log.validate()
+    }
+}
+
+fn default_anchor_interval() -> u64 {
+    3600
+}
+
+fn default_anchor_method() -> String {
+    String::from("auditor_anchor")
+}
+
+fn default_epoch_length() -> u64 {
+    86400
+}
+
+fn default_expected_interval() -> u64 {
+    11
+}
+
+fn default_gap_policy() -> ServerGapPolicy {
+    ServerGapPolicy::None
+}
+
+fn default_liveness_check_interval() -> u64 {
+    30
+}
+
+fn default_liveness_late_after() -> u64 {
+    3
+}
+
+fn default_liveness_missing_after() -> u64 {
+    30
+}
+
+fn default_max_sample_age() -> u64 {
+    3600
+}
+
+fn default_network_reference_throughput() -> f64 {
+    125000000.0
 }
 
-pub fn parse(etc_dir: &str) -> ServerConfig {
+fn default_shutdown_timeout() -> u64 {
+    30
+}
+
+fn default_webhooks_max_attempts() -> u32 {
+    10
+}
+
+fn default_webhooks_signature_failure_threshold() -> u64 {
+    5
+}
+
+fn default_webhooks_validation_rejection_threshold() -> u64 {
+    20
+}
+
+fn load(config: &Config) -> Result<(ServerConfig, ConfigSources), String> {
     let config_file_name: String = format!(
         "{etc_dir}/{config_file}",
-        etc_dir = etc_dir,
+        etc_dir = config.etc_dir(),
         config_file = "auditor_server.toml"
     );
-    let config_file_contents: String =
-        fs::read_to_string(&config_file_name).expect("Can not open configuration file.");
-    let config_file_parameters: ServerConfig = toml::from_str(&config_file_contents).unwrap();
+    let (config_parameters, sources): (ServerConfig, ConfigSources) =
+        common_config::load(&config_file_name, config.overrides())?;
+
+    common_config::check(config_parameters.validate(), &sources)?;
+
+    Ok((config_parameters, sources))
+}
+
+pub fn parse(config: &Config) -> Result<ServerConfig, String> {
+    load(config).map(|(config_parameters, _)| config_parameters)
+}
+
+pub fn print(config: &Config) -> Result<(), String> {
+    let (config_parameters, sources): (ServerConfig, ConfigSources) = load(config)?;
 
-    config_file_parameters
+    common_config::print(&config_parameters, &sources)
 }
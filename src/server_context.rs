@@ -0,0 +1,69 @@
+use crate::config::Config;
+use crate::db::Db;
+use crate::de::De;
+use crate::info::Info;
+use crate::server_audit::AuditLog;
+use crate::server_config::ServerConfig;
+use crate::server_webhook::Webhooks;
+use std::sync::{Arc, Mutex, RwLock};
+
+#[derive(Clone, Debug)]
+pub struct ServerContext {
+    audit_log: AuditLog,
+    config: Config,
+    database: Arc<Mutex<Db>>,
+    de: De,
+    info: Info,
+    server_config: Arc<RwLock<ServerConfig>>,
+    webhooks: Webhooks,
+}
+
+impl ServerContext {
+    pub fn new(
+        audit_log: AuditLog,
+        config: Config,
+        database: Arc<Mutex<Db>>,
+        de: De,
+        info: Info,
+        server_config: Arc<RwLock<ServerConfig>>,
+        webhooks: Webhooks,
+    ) -> Self {
+        Self {
+            audit_log,
+            config,
+            database,
+            de,
+            info,
+            server_config,
+            webhooks,
+        }
+    }
+
+    pub fn audit_log(&self) -> &AuditLog {
+        &self.audit_log
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn database(&self) -> &Arc<Mutex<Db>> {
+        &self.database
+    }
+
+    pub fn de(&self) -> &De {
+        &self.de
+    }
+
+    pub fn info(&self) -> &Info {
+        &self.info
+    }
+
+    pub fn server_config(&self) -> ServerConfig {
+        self.server_config.read().unwrap().clone()
+    }
+
+    pub fn webhooks(&self) -> &Webhooks {
+        &self.webhooks
+    }
+}
@@ -1,181 +1,521 @@
+use crate::common_certificate;
+use crate::common_metric;
+use crate::common_metric::MetricValue;
 use crate::common_request;
-use crate::config::Config;
 use crate::db::Db;
+use crate::db_chain;
+use crate::db_epoch::DbEpoch;
 use crate::db_record::DbRecord;
 use crate::db_statistics::DbStatistics;
 use crate::de;
 use crate::de::De;
 use crate::info::Info;
 use crate::request_post_statistics::RequestPostStatistics;
+use crate::response_get_host::ResponseGetHost;
+use crate::response_get_hosts::{HostData, ResponseGetHosts};
+use crate::response_get_inclusion_proof::ResponseGetInclusionProof;
 use crate::response_get_proof_of_computation::{
     ProofOfComputationData, ResponseGetProofOfComputation,
 };
+use crate::response_get_report::ResponseGetReport;
+use crate::response_get_statistics::ResponseGetStatistics;
 use crate::response_post_statistics::ResponsePostStatistics;
-use hyper::{body::HttpBody, Body, Request, Response, StatusCode};
-use log::warn;
+use crate::server_audit::AuditLog;
+use crate::server_config::ServerConfig;
+use crate::server_context::ServerContext;
+use crate::server_liveness;
+use crate::server_merkle;
+use crate::server_metrics::ServerMetrics;
+use crate::server_proof;
+use crate::server_report;
+use crate::server_scoring;
+use crate::server_webhook::Webhooks;
+use hyper::{Body, Request, Response, StatusCode};
+use log::{info, warn};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::num::ParseIntError;
 use std::sync::{Arc, Mutex};
 use std::time::SystemTime;
 use uuid::Uuid;
 
-pub async fn handle_get_proof_of_computation(
-    config: Config,
-    database: Arc<Mutex<Db>>,
-    de: De,
-    info: Info,
-    request: Request<Body>,
+const LIMIT_DEFAULT: usize = 100;
+const LIMIT_MAX: usize = 1000;
+
+#[derive(Clone, Copy, Debug)]
+pub struct ValidationRejection;
+
+pub async fn handle_get_host(
+    context: ServerContext,
+    _request: Request<Body>,
+    host_id: String,
 ) -> Result<Response<Body>, hyper::Error> {
-    let path: &str = request.uri().path();
-    let host_id: &str = path.strip_prefix("/proof-of-computation/").unwrap();
-    let mut database = database.lock().unwrap();
-    let db_record: Option<&DbRecord> = database.get(host_id);
+    let (de, info): (&De, &Info) = (context.de(), context.info());
+    let server_config: ServerConfig = context.server_config();
+    let database = context.database().lock().unwrap();
 
-    if let Some(db_record) = db_record {
-        let mut new_db_record: DbRecord = db_record.clone();
-        let mut data: Vec<ProofOfComputationData> = vec![];
-        let mut partial_proof_of_computation_array: Vec<f64> = vec![];
-        let proof_of_computation: f64;
+    if let Some(db_record) = database.get(&host_id) {
+        let response_get_host: ResponseGetHost = ResponseGetHost::new(
+            get_host_data(&host_id, db_record, &server_config),
+            context.config().host_id().to_string(),
+            de.public_key_string(),
+        );
+        let body: String = serde_json::to_string(&response_get_host).unwrap();
 
-        for statistic in new_db_record.statistics() {
-            if !statistic.used_for_proof() {
-                let mut cpu_usage: f64 = *statistic.cpu_usage() as f64 / 100.0;
+        return Ok(response(de, info, StatusCode::OK, body));
+    }
 
-                if cpu_usage == 0.0 {
-                    cpu_usage = 0.01;
-                }
+    Ok(response(
+        de,
+        info,
+        StatusCode::NOT_FOUND,
+        String::from("{}"),
+    ))
+}
 
-                let mut mem_usage: f64 = *statistic.mem_usage() as f64 / 100.0;
+pub async fn handle_get_hosts(
+    context: ServerContext,
+    request: Request<Body>,
+) -> Result<Response<Body>, hyper::Error> {
+    let (de, info): (&De, &Info) = (context.de(), context.info());
+    let server_config: ServerConfig = context.server_config();
+    let query_parameters: HashMap<String, String> = common_request::get_query_parameters(&request);
+    let limit: usize = query_parameters
+        .get("limit")
+        .and_then(|limit| limit.parse().ok())
+        .unwrap_or(LIMIT_DEFAULT)
+        .clamp(1, LIMIT_MAX);
+    let cursor: Option<&String> = query_parameters.get("cursor");
+    let database = context.database().lock().unwrap();
+    let mut host_ids: Vec<&String> = database
+        .iter()
+        .map(|(host_id, _)| host_id)
+        .filter(|host_id| match cursor {
+            Some(cursor) => *host_id > cursor,
+            None => true,
+        })
+        .collect();
+    host_ids.sort();
 
-                if mem_usage == 0.0 {
-                    mem_usage = 0.01;
-                }
+    let next_cursor: Option<String> = if host_ids.len() > limit {
+        Some(host_ids[limit - 1].to_string())
+    } else {
+        None
+    };
+    let data: Vec<HostData> = host_ids
+        .iter()
+        .take(limit)
+        .filter_map(|host_id| {
+            database
+                .get(host_id)
+                .map(|db_record| get_host_data(host_id, db_record, &server_config))
+        })
+        .collect();
+    let response_get_hosts: ResponseGetHosts = ResponseGetHosts::new(
+        data,
+        context.config().host_id().to_string(),
+        next_cursor,
+        de.public_key_string(),
+    );
+    let body: String = serde_json::to_string(&response_get_hosts).unwrap();
 
-                let mut mt_2_result_multiplier: f64 = 1.0 - statistic.mt_2_result();
+    Ok(response(de, info, StatusCode::OK, body))
+}
 
-                if mt_2_result_multiplier <= 0.0 {
-                    mt_2_result_multiplier = 0.01;
-                }
+pub async fn handle_get_host_statistics(
+    context: ServerContext,
+    request: Request<Body>,
+    host_id: String,
+) -> Result<Response<Body>, hyper::Error> {
+    let (de, info): (&De, &Info) = (context.de(), context.info());
+    let query_parameters: HashMap<String, String> = common_request::get_query_parameters(&request);
+    let from: Option<u64> = match get_u64_parameter(&query_parameters, "from") {
+        Ok(from) => from,
+        Err(_) => {
+            return Ok(response(
+                de,
+                info,
+                StatusCode::BAD_REQUEST,
+                String::from("{}"),
+            ))
+        }
+    };
+    let to: Option<u64> = match get_u64_parameter(&query_parameters, "to") {
+        Ok(to) => to,
+        Err(_) => {
+            return Ok(response(
+                de,
+                info,
+                StatusCode::BAD_REQUEST,
+                String::from("{}"),
+            ))
+        }
+    };
+    let limit: usize = query_parameters
+        .get("limit")
+        .and_then(|limit| limit.parse().ok())
+        .unwrap_or(LIMIT_DEFAULT)
+        .clamp(1, LIMIT_MAX);
+    let cursor: Option<&String> = query_parameters.get("cursor");
+    let fields: Option<Vec<&str>> = query_parameters
+        .get("fields")
+        .map(|fields| fields.split(',').map(|field| field.trim()).collect());
+    let database = context.database().lock().unwrap();
+    let db_record: &DbRecord = match database.get(&host_id) {
+        Some(db_record) => db_record,
+        None => {
+            return Ok(response(
+                de,
+                info,
+                StatusCode::NOT_FOUND,
+                String::from("{}"),
+            ))
+        }
+    };
+    let mut statistics: Vec<&DbStatistics> = db_record
+        .statistics()
+        .iter()
+        .filter(|statistic| from.is_none_or(|from| *statistic.timestamp() >= from))
+        .filter(|statistic| to.is_none_or(|to| *statistic.timestamp() < to))
+        .collect();
 
-                let mut mt_4_result_multiplier: f64 = 1.0 - statistic.mt_4_result();
+    if let Some(cursor) = cursor {
+        match statistics
+            .iter()
+            .position(|statistic| statistic.id() == cursor)
+        {
+            Some(position) => statistics = statistics.split_off(position + 1),
+            None => {
+                return Ok(response(
+                    de,
+                    info,
+                    StatusCode::BAD_REQUEST,
+                    String::from("{}"),
+                ))
+            }
+        }
+    }
 
-                if mt_4_result_multiplier <= 0.0 {
-                    mt_4_result_multiplier = 0.01;
-                }
+    let next_cursor: Option<String> = if statistics.len() > limit {
+        Some(statistics[limit - 1].id().to_string())
+    } else {
+        None
+    };
+    let data: Vec<Value> = statistics
+        .iter()
+        .take(limit)
+        .map(|statistic| select_fields(statistic, &fields))
+        .collect();
+    let response_get_statistics: ResponseGetStatistics = ResponseGetStatistics::new(
+        data,
+        context.config().host_id().to_string(),
+        next_cursor,
+        de.public_key_string(),
+    );
+    let body: String = serde_json::to_string(&response_get_statistics).unwrap();
 
-                let mut mt_8_result_multiplier: f64 = 1.0 - statistic.mt_8_result();
+    Ok(response(de, info, StatusCode::OK, body))
+}
 
-                if mt_8_result_multiplier <= 0.0 {
-                    mt_8_result_multiplier = 0.01;
-                }
+pub async fn handle_get_metrics(
+    database: Arc<Mutex<Db>>,
+    server_metrics: Arc<ServerMetrics>,
+) -> Result<Response<Body>, hyper::Error> {
+    let body: String = server_metrics.render(&database.lock().unwrap());
 
-                let mut st_result_multiplier: f64 = 1.0 - statistic.st_result();
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "text/plain; version=0.0.4")
+        .body(Body::from(body))
+        .expect("response builder"))
+}
 
-                if st_result_multiplier <= 0.0 {
-                    st_result_multiplier = 0.01;
-                }
+pub async fn handle_not_found(context: ServerContext) -> Result<Response<Body>, hyper::Error> {
+    Ok(response(
+        context.de(),
+        context.info(),
+        StatusCode::NOT_FOUND,
+        String::from("{}"),
+    ))
+}
 
-                let mut sys_load_average_fifteen_multiplier: f64 =
-                    *statistic.sys_load_average_fifteen() as f64;
+pub async fn handle_get_inclusion_proof(
+    context: ServerContext,
+    request: Request<Body>,
+    host_id: String,
+    statistic_id: String,
+) -> Result<Response<Body>, hyper::Error> {
+    let (de, info): (&De, &Info) = (context.de(), context.info());
+    let server_config: ServerConfig = context.server_config();
+    let query_parameters: HashMap<String, String> = common_request::get_query_parameters(&request);
+    let epoch_length: u64 = (*server_config.epoch_length()).max(1);
+    let now: u64 = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let (from, to): (Option<u64>, Option<u64>) =
+        match get_bounds_parameters(&query_parameters, epoch_length) {
+            Some(bounds) => bounds,
+            None => {
+                return Ok(response(
+                    de,
+                    info,
+                    StatusCode::BAD_REQUEST,
+                    String::from("{}"),
+                ))
+            }
+        };
+    let (window, epoch, frozen): (Option<(u64, u64)>, Option<u64>, bool) =
+        server_proof::get_window(from, to, epoch_length, now);
+    let (window_from, window_to): (u64, u64) = window.unwrap_or((0, now));
+    let database = context.database().lock().unwrap();
 
-                if sys_load_average_fifteen_multiplier <= 0.0 {
-                    sys_load_average_fifteen_multiplier = 0.01;
-                }
+    if let Some(db_record) = database.get(&host_id) {
+        let db_epoch: Option<&DbEpoch> = db_record
+            .epochs()
+            .iter()
+            .find(|db_epoch| frozen && Some(*db_epoch.epoch()) == epoch);
+        let statistics: Vec<&DbStatistics> = match (db_epoch, window) {
+            (None, Some((from, to))) => {
+                server_proof::select_window(db_record, &host_id, from, to, &server_config, now).0
+            }
+            _ => server_proof::select(db_record, db_epoch, window),
+        };
+        let leaves: Vec<String> = server_proof::merkle_leaves(&statistics);
 
-                let mut sys_load_average_five_multiplier: f64 =
-                    *statistic.sys_load_average_five() as f64;
+        if let Some(index) = statistics
+            .iter()
+            .position(|statistic| *statistic.id() == statistic_id)
+        {
+            let response_get_inclusion_proof: ResponseGetInclusionProof =
+                ResponseGetInclusionProof::new(
+                    statistics[index].clone(),
+                    epoch,
+                    window_from,
+                    context.config().host_id().to_string(),
+                    index,
+                    leaves[index].to_string(),
+                    server_merkle::root(&leaves),
+                    server_merkle::path(&leaves, index),
+                    de.public_key_string(),
+                    window_to,
+                );
+            let body: String = format_body(
+                de,
+                &query_parameters,
+                serde_json::to_string(&response_get_inclusion_proof).unwrap(),
+            );
 
-                if sys_load_average_five_multiplier <= 0.0 {
-                    sys_load_average_five_multiplier = 0.01;
-                }
+            return Ok(response(de, info, StatusCode::OK, body));
+        }
+    }
 
-                let mut sys_load_average_one_multiplier: f64 =
-                    *statistic.sys_load_average_one() as f64;
+    Ok(response(
+        de,
+        info,
+        StatusCode::NOT_FOUND,
+        String::from("{}"),
+    ))
+}
 
-                if sys_load_average_one_multiplier <= 0.0 {
-                    sys_load_average_one_multiplier = 0.01;
-                }
+pub async fn handle_get_proof_of_computation(
+    context: ServerContext,
+    request: Request<Body>,
+    host_id: String,
+) -> Result<Response<Body>, hyper::Error> {
+    let (de, info): (&De, &Info) = (context.de(), context.info());
+    let server_config: ServerConfig = context.server_config();
+    let query_parameters: HashMap<String, String> = common_request::get_query_parameters(&request);
+    let epoch_length: u64 = (*server_config.epoch_length()).max(1);
+    let now: u64 = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let (from, to): (Option<u64>, Option<u64>) =
+        match get_bounds_parameters(&query_parameters, epoch_length) {
+            Some(bounds) => bounds,
+            None => {
+                return Ok(response(
+                    de,
+                    info,
+                    StatusCode::BAD_REQUEST,
+                    String::from("{}"),
+                ))
+            }
+        };
 
-                let partial_proof_of_computation: f64 = cpu_usage
-                    * mem_usage
-                    * mt_2_result_multiplier
-                    * mt_4_result_multiplier
-                    * mt_8_result_multiplier
-                    * st_result_multiplier
-                    * sys_load_average_fifteen_multiplier
-                    * sys_load_average_five_multiplier
-                    * sys_load_average_one_multiplier
-                    * statistic.sys_uptime();
-                let proof_of_computation_data: ProofOfComputationData = ProofOfComputationData::new(
-                    statistic.id().to_string(),
-                    partial_proof_of_computation,
-                );
+    let (window, epoch, frozen): (Option<(u64, u64)>, Option<u64>, bool) =
+        server_proof::get_window(from, to, epoch_length, now);
+    let (window_from, window_to): (u64, u64) = window.unwrap_or((0, now));
+    let mut database = context.database().lock().unwrap();
+    let db_record: Option<&DbRecord> = database.get(&host_id);
 
-                data.push(proof_of_computation_data);
+    if let Some(db_record) = db_record {
+        let mut new_db_record: DbRecord = db_record.clone();
+        let db_epoch: Option<&DbEpoch> = db_record
+            .epochs()
+            .iter()
+            .find(|db_epoch| frozen && Some(*db_epoch.epoch()) == epoch);
+        let (statistics, scale): (Vec<&DbStatistics>, f64) = match (db_epoch, window) {
+            (None, Some((from, to))) => {
+                let (statistics, _, scale): (Vec<&DbStatistics>, Vec<(u64, u64)>, f64) =
+                    server_proof::select_window(db_record, &host_id, from, to, &server_config, now);
+                (statistics, scale)
             }
-        }
+            _ => (server_proof::select(db_record, db_epoch, window), 1.0),
+        };
+        let merkle_root: String = server_merkle::root(&server_proof::merkle_leaves(&statistics));
+        let (data, proof_of_computation): (Vec<ProofOfComputationData>, f64) = match db_epoch {
+            Some(db_epoch) => (db_epoch.data().to_vec(), *db_epoch.proof_of_computation()),
+            None => server_proof::compute(&statistics, scale, server_config.scoring()),
+        };
 
-        for d in &data {
-            partial_proof_of_computation_array.push(*d.partial_proof_of_computation());
+        if frozen && db_epoch.is_none() {
+            server_proof::freeze(
+                &mut new_db_record,
+                &host_id,
+                epoch.unwrap_or(0),
+                window_from,
+                window_to,
+                &server_config,
+                now,
+            );
+            database.remove(&host_id);
+            database.insert(host_id.to_string(), new_db_record);
+            database.save();
         }
 
-        proof_of_computation = partial_proof_of_computation_array.iter().sum::<f64>();
+        drop(database);
+
+        let sample_count: usize = data.len();
+        let proof_issued: Value = serde_json::json!({
+            "epoch": epoch,
+            "frozen": frozen,
+            "from": window_from,
+            "host_id": host_id,
+            "merkle_root": merkle_root,
+            "proof_of_computation": proof_of_computation,
+            "sample_count": sample_count,
+            "to": window_to,
+        });
+
+        context
+            .audit_log()
+            .record("proof.issued", proof_issued.clone());
+        context.webhooks().emit("proof.issued", proof_issued);
 
         let response_get_proof_of_computation: ResponseGetProofOfComputation =
             ResponseGetProofOfComputation::new(
                 data,
-                config.host_id().to_string(),
+                epoch,
+                window_from,
+                frozen,
+                context.config().host_id().to_string(),
+                merkle_root,
                 proof_of_computation,
                 de.public_key_string(),
+                sample_count,
+                window_to,
             );
-        let body: String = serde_json::to_string(&response_get_proof_of_computation).unwrap();
-        let signature: String = de.sign(&body);
-        let respose: Response<Body> = Response::builder()
-            .status(StatusCode::CREATED)
-            .header("content-type", "application/json")
-            .header("signature", signature)
-            .header("user-agent", format!("{}/{}", info.name(), info.version()))
-            .body(Body::from(body))
-            .expect("request builder");
+        let body: String = format_body(
+            de,
+            &query_parameters,
+            serde_json::to_string(&response_get_proof_of_computation).unwrap(),
+        );
 
-        return Ok(respose);
+        return Ok(response(de, info, StatusCode::CREATED, body));
     }
 
-    let body: String = String::from("{}");
-    let signature: String = de.sign(&body);
-    let not_found: Response<Body> = Response::builder()
-        .status(StatusCode::NOT_FOUND)
-        .header("content-type", "application/json")
-        .header("signature", signature)
-        .header("user-agent", format!("{}/{}", info.name(), info.version()))
-        .body(Body::from(body))
-        .expect("request builder");
+    Ok(response(
+        de,
+        info,
+        StatusCode::NOT_FOUND,
+        String::from("{}"),
+    ))
+}
+
+pub async fn handle_get_report(
+    context: ServerContext,
+    request: Request<Body>,
+) -> Result<Response<Body>, hyper::Error> {
+    let (de, info): (&De, &Info) = (context.de(), context.info());
+    let server_config: ServerConfig = context.server_config();
+    let query_parameters: HashMap<String, String> = common_request::get_query_parameters(&request);
+    let epoch_length: u64 = (*server_config.epoch_length()).max(1);
+    let now: u64 = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let (from, to): (Option<u64>, Option<u64>) =
+        match get_bounds_parameters(&query_parameters, epoch_length) {
+            Some(bounds) => bounds,
+            None => {
+                return Ok(response(
+                    de,
+                    info,
+                    StatusCode::BAD_REQUEST,
+                    String::from("{}"),
+                ))
+            }
+        };
 
-    Ok(not_found)
+    let database = context.database().lock().unwrap();
+    let response_get_report: ResponseGetReport = server_report::get(
+        context.config(),
+        &database,
+        de,
+        from.unwrap_or(0),
+        &server_config,
+        to.unwrap_or(now),
+    );
+    let body: String = format_body(
+        de,
+        &query_parameters,
+        serde_json::to_string(&response_get_report).unwrap(),
+    );
+
+    Ok(response(de, info, StatusCode::OK, body))
 }
 
 pub async fn handle_post_statistics(
-    config: Config,
-    database: Arc<Mutex<Db>>,
-    de: De,
-    info: Info,
+    context: ServerContext,
     request: Request<Body>,
 ) -> Result<Response<Body>, hyper::Error> {
+    let (audit_log, webhooks): (&AuditLog, &Webhooks) = (context.audit_log(), context.webhooks());
+    let (de, info): (&De, &Info) = (context.de(), context.info());
+    let server_config: ServerConfig = context.server_config();
     let signature: String = common_request::get_signature_from_request(&request);
-    let body_vector: Vec<u8> = request.into_body().data().await.unwrap().unwrap().to_vec();
-    let body: String = String::from_utf8(body_vector).unwrap();
-    let request_post_statistics: RequestPostStatistics = serde_json::from_str(&body).unwrap();
+    let previous_signature: Option<String> =
+        common_request::get_previous_signature_from_request(&request);
+    let body_bytes = hyper::body::to_bytes(request.into_body()).await?;
+    let request_post_statistics: Result<(String, RequestPostStatistics), String> =
+        String::from_utf8(body_bytes.to_vec())
+            .map_err(|x| x.to_string())
+            .and_then(|body| {
+                let request_post_statistics: RequestPostStatistics =
+                    serde_json::from_str(&body).map_err(|x| x.to_string())?;
+                validate_post_statistics(&request_post_statistics)?;
+                Ok((body, request_post_statistics))
+            });
+    let (body, request_post_statistics): (String, RequestPostStatistics) =
+        match request_post_statistics {
+            Ok(request_post_statistics) => request_post_statistics,
+            Err(x) => {
+                let mut bad_request: Response<Body> =
+                    response(de, info, StatusCode::BAD_REQUEST, String::from("{}"));
+
+                warn!("Invalid statistics: {}", x);
+                bad_request.extensions_mut().insert(ValidationRejection);
+
+                return Ok(bad_request);
+            }
+        };
     let signature_correct: bool =
         de::check_sign(&body, request_post_statistics.public_key(), &signature);
 
     if !signature_correct {
-        let body: String = String::from("{}");
-        let signature: String = de.sign(&body);
-        let unauthenticated = Response::builder()
-            .status(StatusCode::UNAUTHORIZED)
-            .header("content-type", "application/json")
-            .header("signature", signature)
-            .header("user-agent", format!("{}/{}", info.name(), info.version()))
-            .body(Body::from(body))
-            .expect("request builder");
+        let unauthenticated: Response<Body> =
+            response(de, info, StatusCode::UNAUTHORIZED, String::from("{}"));
 
         warn!(
             "Incorrect signature for host = {}",
@@ -186,88 +526,610 @@ pub async fn handle_post_statistics(
     }
 
     let host_id: String = request_post_statistics.host_id().to_string();
-    let mut database = database.lock().unwrap();
-    let db_statistics: DbStatistics = DbStatistics::new(
+
+    if server_config
+        .enrollment()
+        .revoked_hosts()
+        .contains(&host_id)
+    {
+        warn!("Rejected statistics from revoked host = {}", host_id);
+
+        return Ok(response(
+            de,
+            info,
+            StatusCode::FORBIDDEN,
+            String::from("{}"),
+        ));
+    }
+
+    let cpu_usage: f32 =
+        match server_scoring::cpu_usage(&request_post_statistics, server_config.scoring()) {
+            Some(cpu_usage) => cpu_usage,
+            None => {
+                warn!("Statistics from host = {} are missing cpu.idle", host_id);
+
+                return Ok(response(
+                    de,
+                    info,
+                    StatusCode::BAD_REQUEST,
+                    String::from("{}"),
+                ));
+            }
+        };
+    let (mem_used_percent, mem_used_percent_estimated): (f32, bool) =
+        match server_scoring::mem_used_percent(&request_post_statistics) {
+            Some(mem_used_percent) => mem_used_percent,
+            None => {
+                warn!(
+                    "Statistics from host = {} are missing memory.used_percent",
+                    host_id
+                );
+
+                return Ok(response(
+                    de,
+                    info,
+                    StatusCode::BAD_REQUEST,
+                    String::from("{}"),
+                ));
+            }
+        };
+    let mut database = context.database().lock().unwrap();
+    let timestamp: u64 = match get_timestamp(
+        *request_post_statistics.collected_at(),
+        database.get(&host_id),
+        &server_config,
+    ) {
+        Ok(timestamp) => timestamp,
+        Err(x) => {
+            warn!("Rejected statistics from host = {}: {}", host_id, x);
+
+            return Ok(response(
+                de,
+                info,
+                StatusCode::BAD_REQUEST,
+                String::from("{}"),
+            ));
+        }
+    };
+    let mut db_statistics: DbStatistics = DbStatistics::new(
         "".to_string(),
+        request_post_statistics.container().clone(),
+        *request_post_statistics.containerized(),
+        request_post_statistics.cpu_cores().to_vec(),
         *request_post_statistics.cpu_count(),
         *request_post_statistics.cpu_idle(),
         *request_post_statistics.cpu_interrupt(),
         *request_post_statistics.cpu_nice(),
         *request_post_statistics.cpu_system(),
-        100.0 - *request_post_statistics.cpu_idle(),
+        cpu_usage,
         *request_post_statistics.cpu_user(),
+        request_post_statistics.disk_io().to_vec(),
+        request_post_statistics.disk_mounts().to_vec(),
         Uuid::new_v4().to_string(),
+        *request_post_statistics.mem_available(),
         *request_post_statistics.mem_free(),
         *request_post_statistics.mem_usage(),
         *request_post_statistics.mem_total(),
+        Some(mem_used_percent),
+        mem_used_percent_estimated,
+        request_post_statistics.metrics().clone(),
+        request_post_statistics.network_interfaces().to_vec(),
         *request_post_statistics.mt_2_result(),
         *request_post_statistics.mt_4_result(),
         *request_post_statistics.mt_8_result(),
+        *request_post_statistics.protocol_version(),
+        0,
         *request_post_statistics.st_result(),
         *request_post_statistics.sys_load_average_fifteen(),
         *request_post_statistics.sys_load_average_five(),
         *request_post_statistics.sys_load_average_one(),
         *request_post_statistics.sys_uptime(),
-        SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs(),
+        timestamp,
         false,
     );
-    let db_statistics_for_response: DbStatistics = db_statistics.clone();
-    let db_record: Option<&DbRecord> = database.get(&host_id);
 
-    match db_record {
-        Some(db_record) => {
-            let mut new_db_record: DbRecord = db_record.clone();
+    let mut rotated: Option<Value> = None;
+
+    match database.get(&host_id) {
+        Some(db_record) if db_record.public_key() != request_post_statistics.public_key() => {
+            let data: Value = serde_json::json!({
+                "host_id": host_id,
+                "previous_public_key": db_record.public_key(),
+                "public_key": request_post_statistics.public_key(),
+            });
+            let previous_signature_correct: bool =
+                previous_signature
+                    .as_ref()
+                    .is_some_and(|previous_signature| {
+                        de::check_sign(&body, db_record.public_key(), previous_signature)
+                    });
 
-            if db_record.public_key() != request_post_statistics.public_key() {
-                let body: String = String::from("{}");
-                let signature: String = de.sign(&body);
-                let unauthenticated = Response::builder()
-                    .status(StatusCode::UNAUTHORIZED)
-                    .header("content-type", "application/json")
-                    .header("signature", signature)
-                    .header("user-agent", format!("{}/{}", info.name(), info.version()))
-                    .body(Body::from(body))
-                    .expect("request builder");
+            if !previous_signature_correct {
+                let unauthenticated: Response<Body> =
+                    response(de, info, StatusCode::UNAUTHORIZED, String::from("{}"));
 
+                drop(database);
                 warn!(
                     "Incorrect signature for host = {}",
                     request_post_statistics.host_id()
                 );
+                audit_log.record("host.key_mismatch", data.clone());
+                webhooks.emit("host.key_mismatch", data);
 
                 return Ok(unauthenticated);
             }
 
-            new_db_record.statistics().push(db_statistics);
+            rotated = Some(data);
+        }
+        Some(_) => {}
+        None => {
+            if *server_config.enrollment().approval_required()
+                && !server_config
+                    .enrollment()
+                    .approved_hosts()
+                    .contains(&host_id)
+            {
+                warn!("Host = {} is awaiting enrollment approval", host_id);
+
+                return Ok(response(
+                    de,
+                    info,
+                    StatusCode::FORBIDDEN,
+                    String::from("{}"),
+                ));
+            }
+        }
+    }
+
+    if !server_scoring::partial_proof_of_computation(&db_statistics, server_config.scoring())
+        .is_finite()
+    {
+        warn!(
+            "Statistics from host = {} give a non-finite proof of computation",
+            host_id
+        );
+
+        return Ok(response(
+            de,
+            info,
+            StatusCode::BAD_REQUEST,
+            String::from("{}"),
+        ));
+    }
+
+    db_chain::link(&mut database, &host_id, &mut db_statistics);
+
+    let db_statistics_for_response: DbStatistics = db_statistics.clone();
+    let db_record: Option<&DbRecord> = database.get(&host_id);
+    let mut first_seen: Option<Value> = None;
+
+    match db_record {
+        Some(db_record) => {
+            let mut new_db_record: DbRecord = db_record.clone();
+
+            if rotated.is_some() {
+                new_db_record.set_public_key(request_post_statistics.public_key().to_string());
+            }
+
+            new_db_record.statistics_mut().push(db_statistics);
             database.remove(&host_id);
             database.insert(host_id, new_db_record);
             database.save();
         }
         None => {
             let statistics: Vec<DbStatistics> = vec![db_statistics];
-            let new_db_record: DbRecord =
-                DbRecord::new(request_post_statistics.public_key().to_string(), statistics);
+            let new_db_record: DbRecord = DbRecord::new(
+                vec![],
+                request_post_statistics.public_key().to_string(),
+                statistics,
+            );
+            first_seen = Some(serde_json::json!({
+                "host_id": host_id,
+                "public_key": request_post_statistics.public_key(),
+            }));
             database.insert(host_id, new_db_record);
             database.save();
         }
     }
 
+    drop(database);
+
+    if let Some(data) = rotated {
+        info!(
+            "Rotated public key for host = {}",
+            request_post_statistics.host_id()
+        );
+        audit_log.record("host.key_rotated", data.clone());
+        webhooks.emit("host.key_rotated", data);
+    }
+
+    if let Some(data) = first_seen {
+        audit_log.record("host.first_seen", data.clone());
+        webhooks.emit("host.enrolled", data);
+    }
+
     let response_post_statistics: ResponsePostStatistics = ResponsePostStatistics::new(
         db_statistics_for_response,
-        config.host_id().to_string(),
+        context.config().host_id().to_string(),
         de.public_key_string(),
     );
     let body: String = serde_json::to_string(&response_post_statistics).unwrap();
+
+    Ok(response(de, info, StatusCode::CREATED, body))
+}
+
+fn validate_post_statistics(request_post_statistics: &RequestPostStatistics) -> Result<(), String> {
+    let mut values: Vec<(String, Option<f64>)> = vec![
+        (
+            String::from("cpu_idle"),
+            request_post_statistics.cpu_idle().map(f64::from),
+        ),
+        (
+            String::from("cpu_interrupt"),
+            request_post_statistics.cpu_interrupt().map(f64::from),
+        ),
+        (
+            String::from("cpu_nice"),
+            request_post_statistics.cpu_nice().map(f64::from),
+        ),
+        (
+            String::from("cpu_system"),
+            request_post_statistics.cpu_system().map(f64::from),
+        ),
+        (
+            String::from("cpu_user"),
+            request_post_statistics.cpu_user().map(f64::from),
+        ),
+        (
+            String::from("mem_usage"),
+            request_post_statistics.mem_usage().map(f64::from),
+        ),
+        (
+            String::from("mem_used_percent"),
+            request_post_statistics.mem_used_percent().map(f64::from),
+        ),
+        (
+            String::from("mt_2_result"),
+            Some(*request_post_statistics.mt_2_result()),
+        ),
+        (
+            String::from("mt_4_result"),
+            Some(*request_post_statistics.mt_4_result()),
+        ),
+        (
+            String::from("mt_8_result"),
+            Some(*request_post_statistics.mt_8_result()),
+        ),
+        (
+            String::from("st_result"),
+            Some(*request_post_statistics.st_result()),
+        ),
+        (
+            String::from("sys_load_average_fifteen"),
+            request_post_statistics
+                .sys_load_average_fifteen()
+                .map(f64::from),
+        ),
+        (
+            String::from("sys_load_average_five"),
+            request_post_statistics
+                .sys_load_average_five()
+                .map(f64::from),
+        ),
+        (
+            String::from("sys_load_average_one"),
+            request_post_statistics
+                .sys_load_average_one()
+                .map(f64::from),
+        ),
+        (
+            String::from("sys_uptime"),
+            *request_post_statistics.sys_uptime(),
+        ),
+    ];
+
+    if let Some(container) = request_post_statistics.container() {
+        values.push((String::from("container.cpu_quota"), *container.cpu_quota()));
+        values.push((
+            String::from("container.cpu_usage"),
+            container.cpu_usage().map(f64::from),
+        ));
+    }
+
+    for cpu_core in request_post_statistics.cpu_cores() {
+        let name = |field: &str| format!("cpu_cores.{}.{}", cpu_core.core(), field);
+
+        values.push((name("frequency"), cpu_core.frequency().map(f64::from)));
+        values.push((name("idle"), Some(f64::from(*cpu_core.idle()))));
+        values.push((name("iowait"), Some(f64::from(*cpu_core.iowait()))));
+        values.push((name("system"), Some(f64::from(*cpu_core.system()))));
+        values.push((name("user"), Some(f64::from(*cpu_core.user()))));
+    }
+
+    for (name, value) in request_post_statistics.metrics() {
+        if let MetricValue::Float(value) = value {
+            values.push((format!("metrics.{}", name), Some(*value)));
+        }
+    }
+
+    for (name, value) in values {
+        if let Some(value) = value {
+            if !value.is_finite() {
+                return Err(format!("{} = {} is not a finite number", name, value));
+            }
+        }
+    }
+
+    if let Some(workload_cpu) =
+        common_metric::get_f64(request_post_statistics.metrics(), "process.workload_cpu")
+    {
+        if !(workload_cpu as f32).is_finite() {
+            return Err(format!(
+                "metrics.process.workload_cpu = {} is out of range",
+                workload_cpu
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn format_body(de: &De, query_parameters: &HashMap<String, String>, body: String) -> String {
+    match query_parameters.get("format").map(|format| format.as_str()) {
+        Some("certificate") => serde_json::to_string(&common_certificate::issue(de, body)).unwrap(),
+        _ => body,
+    }
+}
+
+fn get_bounds_parameters(
+    query_parameters: &HashMap<String, String>,
+    epoch_length: u64,
+) -> Option<(Option<u64>, Option<u64>)> {
+    server_report::get_bounds(
+        get_u64_parameter(query_parameters, "epoch").ok()?,
+        get_u64_parameter(query_parameters, "from").ok()?,
+        get_u64_parameter(query_parameters, "to").ok()?,
+        epoch_length,
+    )
+}
+
+fn get_host_data(host_id: &str, db_record: &DbRecord, server_config: &ServerConfig) -> HostData {
+    let now: u64 = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let timestamps = db_record
+        .statistics()
+        .iter()
+        .map(|statistic| *statistic.timestamp());
+
+    HostData::new(
+        server_liveness::get_expected_interval(server_config, host_id),
+        timestamps.clone().min(),
+        host_id.to_string(),
+        timestamps.max(),
+        server_liveness::get_state(db_record, host_id, server_config, now),
+        de::fingerprint(db_record.public_key()),
+        db_record.statistics().len(),
+    )
+}
+
+fn get_timestamp(
+    collected_at: u64,
+    db_record: Option<&DbRecord>,
+    server_config: &ServerConfig,
+) -> Result<u64, String> {
+    let now: u64 = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    if collected_at == 0 {
+        return Ok(now);
+    }
+
+    if now.saturating_sub(collected_at) > *server_config.max_sample_age() {
+        return Err(format!(
+            "collected_at = {} is older than {} seconds",
+            collected_at,
+            server_config.max_sample_age()
+        ));
+    }
+
+    let earliest: u64 = db_record
+        .map(|db_record| {
+            let last_timestamp: u64 = db_record
+                .statistics()
+                .iter()
+                .map(|statistic| *statistic.timestamp())
+                .max()
+                .unwrap_or(0);
+            let frozen_until: u64 = db_record
+                .epochs()
+                .iter()
+                .map(|db_epoch| *db_epoch.to())
+                .max()
+                .unwrap_or(0);
+
+            last_timestamp.max(frozen_until)
+        })
+        .unwrap_or(0);
+
+    Ok(collected_at.min(now).max(earliest))
+}
+
+fn get_u64_parameter(
+    query_parameters: &HashMap<String, String>,
+    name: &str,
+) -> Result<Option<u64>, ParseIntError> {
+    match query_parameters.get(name) {
+        Some(value) => value.parse().map(Some),
+        None => Ok(None),
+    }
+}
+
+fn select_fields(statistic: &DbStatistics, fields: &Option<Vec<&str>>) -> Value {
+    let value: Value = serde_json::to_value(statistic).unwrap();
+
+    match (fields, value) {
+        (Some(fields), Value::Object(object)) => Value::Object(
+            object
+                .into_iter()
+                .filter(|(key, _)| {
+                    key == "id" || key == "timestamp" || fields.contains(&key.as_str())
+                })
+                .collect(),
+        ),
+        (_, value) => value,
+    }
+}
+
+fn response(de: &De, info: &Info, status: StatusCode, body: String) -> Response<Body> {
     let signature: String = de.sign(&body);
-    let created: Response<Body> = Response::builder()
-        .status(StatusCode::CREATED)
+
+    Response::builder()
+        .status(status)
         .header("content-type", "application/json")
         .header("signature", signature)
         .header("user-agent", format!("{}/{}", info.name(), info.version()))
         .body(Body::from(body))
-        .expect("request builder");
+        .expect("request builder")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn get_request_post_statistics(fields: Value) -> RequestPostStatistics {
+        let mut value: Value = json!({
+            "host_id": "host",
+            "mt_2_result": 1.0,
+            "mt_4_result": 1.0,
+            "mt_8_result": 1.0,
+            "public_key": "",
+            "st_result": 1.0,
+        });
+
+        if let (Value::Object(object), Value::Object(fields)) = (&mut value, fields) {
+            object.extend(fields);
+        }
+
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn get_server_config() -> ServerConfig {
+        serde_json::from_value(json!({"address": "127.0.0.1", "port": 8081})).unwrap()
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
 
-    Ok(created)
+    #[test]
+    fn get_timestamp_uses_collected_at() {
+        let collected_at: u64 = now() - 60;
+
+        assert_eq!(
+            get_timestamp(collected_at, None, &get_server_config()),
+            Ok(collected_at)
+        );
+    }
+
+    #[test]
+    fn get_timestamp_rejects_old_sample() {
+        assert!(get_timestamp(now() - 7200, None, &get_server_config()).is_err());
+    }
+
+    #[test]
+    fn get_timestamp_does_not_precede_last_statistic() {
+        let last_timestamp: u64 = now() - 30;
+        let db_record: DbRecord = serde_json::from_value(json!({
+            "epochs": [],
+            "public_key": "",
+            "statistics": [{
+                "blockchain_hash": "",
+                "cpu_usage": 0.0,
+                "id": "",
+                "mt_2_result": 1.0,
+                "mt_4_result": 1.0,
+                "mt_8_result": 1.0,
+                "st_result": 1.0,
+                "timestamp": last_timestamp,
+                "used_for_proof": false,
+            }],
+        }))
+        .unwrap();
+
+        assert_eq!(
+            get_timestamp(now() - 60, Some(&db_record), &get_server_config()),
+            Ok(last_timestamp)
+        );
+    }
+
+    #[test]
+    fn validate_post_statistics_accepts_missing_metrics() {
+        assert!(validate_post_statistics(&get_request_post_statistics(json!({}))).is_ok());
+    }
+
+    #[test]
+    fn validate_post_statistics_accepts_any_host_id_and_range() {
+        let request_post_statistics: RequestPostStatistics = get_request_post_statistics(json!({
+            "cpu_idle": 150.0,
+            "host_id": "not-a-uuid",
+            "mem_total": 0,
+            "mem_usage": 2.0,
+        }));
+
+        assert!(validate_post_statistics(&request_post_statistics).is_ok());
+    }
+
+    #[test]
+    fn validate_post_statistics_rejects_infinite_metric() {
+        let request_post_statistics: RequestPostStatistics =
+            get_request_post_statistics(json!({"cpu_idle": 1e39}));
+
+        assert_eq!(
+            validate_post_statistics(&request_post_statistics),
+            Err(String::from("cpu_idle = inf is not a finite number"))
+        );
+    }
+
+    #[test]
+    fn validate_post_statistics_rejects_infinite_cpu_core() {
+        let request_post_statistics: RequestPostStatistics = get_request_post_statistics(json!({
+            "cpu_cores": [{
+                "core": 1,
+                "frequency": null,
+                "idle": 1e39,
+                "iowait": 0.0,
+                "system": 0.0,
+                "user": 0.0,
+            }],
+        }));
+
+        assert_eq!(
+            validate_post_statistics(&request_post_statistics),
+            Err(String::from(
+                "cpu_cores.1.idle = inf is not a finite number"
+            ))
+        );
+    }
+
+    #[test]
+    fn validate_post_statistics_rejects_workload_cpu_out_of_f32_range() {
+        let request_post_statistics: RequestPostStatistics = get_request_post_statistics(json!({
+            "metrics": {"process.workload_cpu": 1e39},
+        }));
+
+        assert_eq!(
+            validate_post_statistics(&request_post_statistics),
+            Err(String::from(
+                "metrics.process.workload_cpu = 1000000000000000000000000000000000000000 is out of range"
+            ))
+        );
+    }
 }
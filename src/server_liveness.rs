@@ -0,0 +1,171 @@
+use crate::common_file;
+use crate::config::Config;
+use crate::db::Db;
+use crate::db_record::DbRecord;
+use crate::server_config::ServerConfig;
+use crate::server_webhook::Webhooks;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LivenessState {
+    Healthy,
+    Late,
+    Missing,
+}
+
+impl LivenessState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LivenessState::Healthy => "healthy",
+            LivenessState::Late => "late",
+            LivenessState::Missing => "missing",
+        }
+    }
+}
+
+pub fn get_expected_interval(server_config: &ServerConfig, host_id: &str) -> u64 {
+    server_config
+        .liveness()
+        .expected_intervals()
+        .get(host_id)
+        .unwrap_or_else(|| server_config.expected_interval())
+        .max(&1)
+        .to_owned()
+}
+
+pub fn get_gaps(timestamps: &[u64], from: u64, to: u64, expected_interval: u64) -> Vec<(u64, u64)> {
+    let mut timestamps: Vec<u64> = timestamps.to_vec();
+    timestamps.sort_unstable();
+
+    let mut gaps: Vec<(u64, u64)> = vec![];
+    let mut previous: u64 = from;
+
+    for timestamp in timestamps.into_iter().chain(std::iter::once(to)) {
+        if timestamp > previous + 2 * expected_interval {
+            gaps.push((previous, timestamp));
+        }
+
+        previous = previous.max(timestamp);
+    }
+
+    gaps
+}
+
+pub fn get_state(
+    db_record: &DbRecord,
+    host_id: &str,
+    server_config: &ServerConfig,
+    now: u64,
+) -> LivenessState {
+    let expected_interval: u64 = get_expected_interval(server_config, host_id);
+    let last_seen: u64 = db_record
+        .statistics()
+        .iter()
+        .map(|statistic| *statistic.timestamp())
+        .max()
+        .unwrap_or(0);
+    let lag: u64 = now.saturating_sub(last_seen);
+
+    if lag <= expected_interval * server_config.liveness().late_after() {
+        LivenessState::Healthy
+    } else if lag <= expected_interval * server_config.liveness().missing_after() {
+        LivenessState::Late
+    } else {
+        LivenessState::Missing
+    }
+}
+
+pub async fn run(
+    config: Config,
+    database: Arc<Mutex<Db>>,
+    server_config: Arc<RwLock<ServerConfig>>,
+    webhooks: Webhooks,
+) {
+    let states_path: String = format!(
+        "{dir}{file}",
+        dir = config.data_dir(),
+        file = "liveness_states.json"
+    );
+    let mut states: HashMap<String, LivenessState> = restore(&states_path);
+
+    loop {
+        let server_config: ServerConfig = server_config.read().unwrap().clone();
+        let check_interval: u64 = (*server_config.liveness().check_interval()).max(1);
+        let now: u64 = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let mut transitions: Vec<(String, Option<LivenessState>, LivenessState)> = vec![];
+
+        {
+            let database = database.lock().unwrap();
+
+            for (host_id, db_record) in database.iter() {
+                let state: LivenessState = get_state(db_record, host_id, &server_config, now);
+                let previous: Option<LivenessState> = states.insert(host_id.to_string(), state);
+
+                if previous != Some(state) {
+                    transitions.push((host_id.to_string(), previous, state));
+                }
+            }
+        }
+
+        if !transitions.is_empty() {
+            save(&states_path, &states);
+        }
+
+        for (host_id, previous, state) in transitions {
+            emit(&host_id, previous, state, &webhooks);
+        }
+
+        tokio::time::sleep(Duration::from_secs(check_interval)).await;
+    }
+}
+
+fn emit(host_id: &str, previous: Option<LivenessState>, state: LivenessState, webhooks: &Webhooks) {
+    match (previous, state) {
+        (None, LivenessState::Healthy) => {}
+        (_, LivenessState::Healthy) => info!("Host = {} is healthy again", host_id),
+        (_, state) => warn!("Host = {} is {}", host_id, state.name()),
+    }
+
+    match (previous, state) {
+        (Some(LivenessState::Missing), LivenessState::Healthy) => {
+            webhooks.emit("host.recovered", serde_json::json!({ "host_id": host_id }))
+        }
+        (_, LivenessState::Missing) => {
+            webhooks.emit("host.missing", serde_json::json!({ "host_id": host_id }))
+        }
+        _ => {}
+    }
+}
+
+fn restore(states_path: &str) -> HashMap<String, LivenessState> {
+    if !Path::new(states_path).exists() {
+        return HashMap::new();
+    }
+
+    match fs::read_to_string(states_path)
+        .map_err(|x| x.to_string())
+        .and_then(|contents| serde_json::from_str(&contents).map_err(|x| x.to_string()))
+    {
+        Ok(states) => states,
+        Err(x) => {
+            warn!("Can not restore liveness states: {}", x);
+            HashMap::new()
+        }
+    }
+}
+
+fn save(states_path: &str, states: &HashMap<String, LivenessState>) {
+    if let Err(x) = common_file::write_atomic(states_path, &serde_json::to_vec(states).unwrap()) {
+        warn!("Can not save liveness states: {}", x);
+    }
+}
@@ -0,0 +1,121 @@
+use crate::db_chain;
+use crate::db_statistics::DbStatistics;
+use crate::de;
+use crate::response_get_inclusion_proof::MerklePathStep;
+
+pub fn leaf(statistic: &DbStatistics) -> String {
+    de::digest(&format!("leaf.{}", db_chain::canonical(statistic)))
+}
+
+pub fn root(leaves: &[String]) -> String {
+    get_levels(leaves)
+        .last()
+        .and_then(|level| level.first())
+        .cloned()
+        .unwrap_or_default()
+}
+
+pub fn path(leaves: &[String], index: usize) -> Vec<MerklePathStep> {
+    let mut path: Vec<MerklePathStep> = vec![];
+    let mut index: usize = index;
+
+    for level in get_levels(leaves).iter().filter(|level| level.len() > 1) {
+        let sibling: usize = index ^ 1;
+
+        if let Some(hash) = level.get(sibling) {
+            let position: &str = if sibling < index { "left" } else { "right" };
+            path.push(MerklePathStep::new(hash.to_string(), position.to_string()));
+        }
+
+        index /= 2;
+    }
+
+    path
+}
+
+fn get_levels(leaves: &[String]) -> Vec<Vec<String>> {
+    let mut levels: Vec<Vec<String>> = vec![];
+
+    if leaves.is_empty() {
+        return levels;
+    }
+
+    levels.push(leaves.to_vec());
+
+    while levels.last().unwrap().len() > 1 {
+        let level: Vec<String> = levels
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => de::digest(&format!("node.{}.{}", left, right)),
+                [single] => single.to_string(),
+                _ => unreachable!(),
+            })
+            .collect();
+
+        levels.push(level);
+    }
+
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn get_leaves(count: usize) -> Vec<String> {
+        (0..count)
+            .map(|index| de::digest(&index.to_string()))
+            .collect()
+    }
+
+    fn fold(leaf: &str, path: &[MerklePathStep]) -> String {
+        path.iter().fold(leaf.to_string(), |hash, step| {
+            let step: Value = serde_json::to_value(step).unwrap();
+            let sibling: &str = step["hash"].as_str().unwrap();
+
+            match step["position"].as_str() {
+                Some("left") => de::digest(&format!("node.{}.{}", sibling, hash)),
+                _ => de::digest(&format!("node.{}.{}", hash, sibling)),
+            }
+        })
+    }
+
+    #[test]
+    fn root_of_no_leaves_is_empty() {
+        assert_eq!(root(&[]), "");
+    }
+
+    #[test]
+    fn root_of_single_leaf_is_the_leaf() {
+        let leaves: Vec<String> = get_leaves(1);
+
+        assert_eq!(root(&leaves), leaves[0]);
+        assert!(path(&leaves, 0).is_empty());
+    }
+
+    #[test]
+    fn root_hashes_pairs_and_promotes_odd_leaf() {
+        let leaves: Vec<String> = get_leaves(3);
+        let node: String = de::digest(&format!("node.{}.{}", leaves[0], leaves[1]));
+
+        assert_eq!(
+            root(&leaves),
+            de::digest(&format!("node.{}.{}", node, leaves[2]))
+        );
+    }
+
+    #[test]
+    fn path_leads_every_leaf_to_the_root() {
+        for count in 1..=9 {
+            let leaves: Vec<String> = get_leaves(count);
+            let root: String = root(&leaves);
+
+            for (index, leaf) in leaves.iter().enumerate() {
+                assert_eq!(fold(leaf, &path(&leaves, index)), root);
+            }
+        }
+    }
+}
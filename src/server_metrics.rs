@@ -0,0 +1,162 @@
+use crate::db::Db;
+use hyper::StatusCode;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+#[derive(Debug, Default)]
+pub struct ServerMetrics {
+    requests: Mutex<BTreeMap<(String, u16), u64>>,
+    signature_failures: AtomicU64,
+    validation_rejections: AtomicU64,
+}
+
+impl ServerMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_request(&self, route: &str, status: StatusCode) {
+        let mut requests = self.requests.lock().unwrap();
+        *requests
+            .entry((route.to_string(), status.as_u16()))
+            .or_insert(0) += 1;
+    }
+
+    pub fn record_signature_failure(&self) {
+        self.signature_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_validation_rejection(&self) {
+        self.validation_rejections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn render(&self, database: &Db) -> String {
+        let now: u64 = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let mut output: String = String::new();
+
+        write_header(
+            &mut output,
+            "auditor_requests_total",
+            "counter",
+            "Requests handled by route and status.",
+        );
+        for ((route, status), count) in self.requests.lock().unwrap().iter() {
+            let _ = writeln!(
+                output,
+                "auditor_requests_total{{route=\"{}\",status=\"{}\"}} {}",
+                route, status, count
+            );
+        }
+
+        write_header(
+            &mut output,
+            "auditor_signature_failures_total",
+            "counter",
+            "Requests rejected because of an incorrect signature.",
+        );
+        let _ = writeln!(
+            output,
+            "auditor_signature_failures_total {}",
+            self.signature_failures.load(Ordering::Relaxed)
+        );
+
+        write_header(
+            &mut output,
+            "auditor_validation_rejections_total",
+            "counter",
+            "Requests rejected because of invalid parameters or payloads.",
+        );
+        let _ = writeln!(
+            output,
+            "auditor_validation_rejections_total {}",
+            self.validation_rejections.load(Ordering::Relaxed)
+        );
+
+        write_header(
+            &mut output,
+            "auditor_db_save_seconds",
+            "summary",
+            "Time spent saving the database.",
+        );
+        let _ = writeln!(
+            output,
+            "auditor_db_save_seconds_sum {}",
+            database.save_duration_total().as_secs_f64()
+        );
+        let _ = writeln!(
+            output,
+            "auditor_db_save_seconds_count {}",
+            database.save_count()
+        );
+
+        write_header(
+            &mut output,
+            "auditor_db_size_bytes",
+            "gauge",
+            "Size of the database file after the last save.",
+        );
+        let _ = writeln!(output, "auditor_db_size_bytes {}", database.save_size());
+
+        write_header(
+            &mut output,
+            "auditor_hosts",
+            "gauge",
+            "Hosts known to the auditor.",
+        );
+        let _ = writeln!(output, "auditor_hosts {}", database.iter().count());
+
+        write_header(
+            &mut output,
+            "auditor_samples",
+            "gauge",
+            "Statistics samples stored.",
+        );
+        let _ = writeln!(
+            output,
+            "auditor_samples {}",
+            database
+                .iter()
+                .map(|(_, db_record)| db_record.statistics().len())
+                .sum::<usize>()
+        );
+
+        write_header(
+            &mut output,
+            "auditor_host_last_seen_lag_seconds",
+            "gauge",
+            "Seconds since the last sample of each host.",
+        );
+        let mut last_seen: Vec<(&String, u64)> = database
+            .iter()
+            .filter_map(|(host_id, db_record)| {
+                db_record
+                    .statistics()
+                    .iter()
+                    .map(|statistic| *statistic.timestamp())
+                    .max()
+                    .map(|timestamp| (host_id, now.saturating_sub(timestamp)))
+            })
+            .collect();
+        last_seen.sort();
+        for (host_id, lag) in last_seen {
+            let _ = writeln!(
+                output,
+                "auditor_host_last_seen_lag_seconds{{host_id=\"{}\"}} {}",
+                host_id, lag
+            );
+        }
+
+        output
+    }
+}
+
+fn write_header(output: &mut String, name: &str, metric_type: &str, help: &str) {
+    let _ = writeln!(output, "# HELP {} {}", name, help);
+    let _ = writeln!(output, "# TYPE {} {}", name, metric_type);
+}
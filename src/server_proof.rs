@@ -0,0 +1,327 @@
+use crate::db_epoch::DbEpoch;
+use crate::db_record::DbRecord;
+use crate::db_statistics::DbStatistics;
+use crate::response_get_proof_of_computation::ProofOfComputationData;
+use crate::server_config::{ServerConfig, ServerGapPolicy, ServerScoringConfig};
+use crate::server_liveness;
+use crate::server_merkle;
+use crate::server_scoring;
+use log::warn;
+use std::collections::HashSet;
+
+pub fn compute(
+    statistics: &[&DbStatistics],
+    scale: f64,
+    scoring_config: &ServerScoringConfig,
+) -> (Vec<ProofOfComputationData>, f64) {
+    let mut data: Vec<ProofOfComputationData> = vec![];
+
+    for statistic in statistics {
+        let mut partial_proof_of_computation: f64 =
+            server_scoring::partial_proof_of_computation(statistic, scoring_config) * scale;
+
+        if !partial_proof_of_computation.is_finite() {
+            warn!(
+                "Statistic = {} gives a non-finite proof of computation, counting it as 0",
+                statistic.id()
+            );
+            partial_proof_of_computation = 0.0;
+        }
+
+        let proof_of_computation_data: ProofOfComputationData =
+            ProofOfComputationData::new(statistic.id().to_string(), partial_proof_of_computation);
+
+        data.push(proof_of_computation_data);
+    }
+
+    let mut proof_of_computation: f64 = data
+        .iter()
+        .fold(0.0, |sum, d| sum + d.partial_proof_of_computation());
+
+    if !proof_of_computation.is_finite() {
+        proof_of_computation = f64::MAX;
+    }
+
+    (data, proof_of_computation)
+}
+
+pub fn get_window(
+    from: Option<u64>,
+    to: Option<u64>,
+    epoch_length: u64,
+    now: u64,
+) -> (Option<(u64, u64)>, Option<u64>, bool) {
+    let window: Option<(u64, u64)> = match (from, to) {
+        (None, None) => None,
+        (from, to) => Some((from.unwrap_or(0), to.unwrap_or(now))),
+    };
+    let epoch: Option<u64> = window
+        .filter(|(from, to)| from.is_multiple_of(epoch_length) && *to == from + epoch_length)
+        .map(|(from, _)| from / epoch_length);
+    let frozen: bool = epoch.is_some() && window.is_some_and(|(_, to)| to <= now);
+
+    (window, epoch, frozen)
+}
+
+pub fn in_window(statistic: &DbStatistics, from: u64, to: u64) -> bool {
+    *statistic.timestamp() >= from && *statistic.timestamp() < to
+}
+
+pub fn merkle_leaves(statistics: &[&DbStatistics]) -> Vec<String> {
+    statistics
+        .iter()
+        .map(|statistic| server_merkle::leaf(statistic))
+        .collect()
+}
+
+pub fn select<'a>(
+    db_record: &'a DbRecord,
+    db_epoch: Option<&DbEpoch>,
+    window: Option<(u64, u64)>,
+) -> Vec<&'a DbStatistics> {
+    db_record
+        .statistics()
+        .iter()
+        .filter(|statistic| match (db_epoch, window) {
+            (Some(db_epoch), _) => db_epoch.data().iter().any(|d| d.id() == statistic.id()),
+            (None, Some((from, to))) => in_window(statistic, from, to),
+            (None, None) => !statistic.used_for_proof(),
+        })
+        .collect()
+}
+
+pub fn freeze(
+    db_record: &mut DbRecord,
+    host_id: &str,
+    epoch: u64,
+    from: u64,
+    to: u64,
+    server_config: &ServerConfig,
+    now: u64,
+) {
+    let (statistics, _, scale): (Vec<&DbStatistics>, Vec<(u64, u64)>, f64) =
+        select_window(db_record, host_id, from, to, server_config, now);
+    let merkle_root: String = server_merkle::root(&merkle_leaves(&statistics));
+    let (data, proof_of_computation): (Vec<ProofOfComputationData>, f64) =
+        compute(&statistics, scale, server_config.scoring());
+
+    for statistic in db_record.statistics_mut() {
+        if in_window(statistic, from, to) {
+            statistic.set_used_for_proof(true);
+        }
+    }
+
+    db_record.epochs_mut().push(DbEpoch::new(
+        vec![],
+        data,
+        epoch,
+        from,
+        merkle_root,
+        proof_of_computation,
+        to,
+    ));
+}
+
+pub fn select_window<'a>(
+    db_record: &'a DbRecord,
+    host_id: &str,
+    from: u64,
+    to: u64,
+    server_config: &ServerConfig,
+    now: u64,
+) -> (Vec<&'a DbStatistics>, Vec<(u64, u64)>, f64) {
+    let expected_interval: u64 = server_liveness::get_expected_interval(server_config, host_id);
+    let mut statistics: Vec<&DbStatistics> = select(db_record, None, Some((from, to)));
+    let timestamps: Vec<u64> = statistics
+        .iter()
+        .map(|statistic| *statistic.timestamp())
+        .collect();
+    let first_seen: u64 = db_record
+        .statistics()
+        .iter()
+        .map(|statistic| *statistic.timestamp())
+        .min()
+        .unwrap_or(to);
+    let start: u64 = from.max(first_seen);
+    let end: u64 = to.min(now);
+    let gaps: Vec<(u64, u64)> =
+        server_liveness::get_gaps(&timestamps, start, end, expected_interval);
+    let mut scale: f64 = 1.0;
+
+    match server_config.liveness().gap_policy() {
+        ServerGapPolicy::Cap => {
+            let mut slots: HashSet<u64> = HashSet::new();
+            statistics.retain(|statistic| slots.insert(statistic.timestamp() / expected_interval));
+        }
+        ServerGapPolicy::None => {}
+        ServerGapPolicy::Scale => {
+            let span: u64 = end.saturating_sub(start);
+            let missed: u64 = gaps
+                .iter()
+                .map(|(from, to)| (to - from).saturating_sub(expected_interval))
+                .sum();
+
+            if span > 0 {
+                scale = 1.0 - missed.min(span) as f64 / span as f64;
+            }
+        }
+    }
+
+    (statistics, gaps, scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn get_db_record(timestamps: &[u64]) -> DbRecord {
+        let statistics: Vec<Value> = timestamps
+            .iter()
+            .enumerate()
+            .map(|(index, timestamp)| {
+                json!({
+                    "blockchain_hash": "",
+                    "cpu_usage": 50.0,
+                    "id": index.to_string(),
+                    "mt_2_result": 1.0,
+                    "mt_4_result": 1.0,
+                    "mt_8_result": 1.0,
+                    "st_result": 1.0,
+                    "timestamp": timestamp,
+                    "used_for_proof": false,
+                })
+            })
+            .collect();
+
+        serde_json::from_value(json!({
+            "epochs": [],
+            "public_key": "",
+            "statistics": statistics,
+        }))
+        .unwrap()
+    }
+
+    fn get_server_config(gap_policy: &str) -> ServerConfig {
+        serde_json::from_value(json!({
+            "address": "127.0.0.1",
+            "expected_interval": 10,
+            "liveness": {"gap_policy": gap_policy},
+            "port": 8081,
+        }))
+        .unwrap()
+    }
+
+    fn get_statistic(sys_uptime: f64, sys_load_average_one: f32) -> DbStatistics {
+        serde_json::from_value(json!({
+            "blockchain_hash": "",
+            "cpu_usage": 100.0,
+            "id": "",
+            "mem_used_percent": 100.0,
+            "mt_2_result": 0.0,
+            "mt_4_result": 0.0,
+            "mt_8_result": 0.0,
+            "st_result": 0.0,
+            "sys_load_average_fifteen": 1.0,
+            "sys_load_average_five": 1.0,
+            "sys_load_average_one": sys_load_average_one,
+            "sys_uptime": sys_uptime,
+            "timestamp": 0,
+            "used_for_proof": false,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn compute_counts_non_finite_partial_proof_as_zero() {
+        let statistics: Vec<DbStatistics> =
+            vec![get_statistic(1e300, 1e20), get_statistic(2.0, 1.0)];
+        let statistics: Vec<&DbStatistics> = statistics.iter().collect();
+        let (data, proof_of_computation): (Vec<ProofOfComputationData>, f64) =
+            compute(&statistics, 1.0, get_server_config("none").scoring());
+
+        assert_eq!(data[0].partial_proof_of_computation(), &0.0);
+        assert_eq!(proof_of_computation, 2.0);
+    }
+
+    #[test]
+    fn compute_clamps_overflowing_proof_of_computation() {
+        let statistics: Vec<DbStatistics> =
+            vec![get_statistic(1e308, 1.0), get_statistic(1e308, 1.0)];
+        let statistics: Vec<&DbStatistics> = statistics.iter().collect();
+        let (_, proof_of_computation): (Vec<ProofOfComputationData>, f64) =
+            compute(&statistics, 1.0, get_server_config("none").scoring());
+
+        assert_eq!(proof_of_computation, f64::MAX);
+    }
+
+    #[test]
+    fn select_window_none_keeps_statistics_and_reports_gaps() {
+        let db_record: DbRecord = get_db_record(&[100, 110, 120, 170, 180]);
+        let (statistics, gaps, scale): (Vec<&DbStatistics>, Vec<(u64, u64)>, f64) = select_window(
+            &db_record,
+            "host",
+            100,
+            190,
+            &get_server_config("none"),
+            1000,
+        );
+
+        assert_eq!(statistics.len(), 5);
+        assert_eq!(gaps, vec![(120, 170)]);
+        assert_eq!(scale, 1.0);
+    }
+
+    #[test]
+    fn select_window_cap_keeps_one_statistic_per_interval() {
+        let db_record: DbRecord = get_db_record(&[100, 101, 105, 110, 120]);
+        let (statistics, _, scale): (Vec<&DbStatistics>, Vec<(u64, u64)>, f64) = select_window(
+            &db_record,
+            "host",
+            100,
+            130,
+            &get_server_config("cap"),
+            1000,
+        );
+        let timestamps: Vec<u64> = statistics
+            .iter()
+            .map(|statistic| *statistic.timestamp())
+            .collect();
+
+        assert_eq!(timestamps, vec![100, 110, 120]);
+        assert_eq!(scale, 1.0);
+    }
+
+    #[test]
+    fn select_window_scale_discounts_missed_time() {
+        let db_record: DbRecord = get_db_record(&[100, 110, 120, 170, 180]);
+        let (statistics, gaps, scale): (Vec<&DbStatistics>, Vec<(u64, u64)>, f64) = select_window(
+            &db_record,
+            "host",
+            100,
+            190,
+            &get_server_config("scale"),
+            1000,
+        );
+
+        assert_eq!(statistics.len(), 5);
+        assert_eq!(gaps, vec![(120, 170)]);
+        assert_eq!(scale, 1.0 - 40.0 / 90.0);
+    }
+
+    #[test]
+    fn select_window_ignores_time_before_first_statistic() {
+        let db_record: DbRecord = get_db_record(&[150, 160, 170]);
+        let (_, gaps, scale): (Vec<&DbStatistics>, Vec<(u64, u64)>, f64) = select_window(
+            &db_record,
+            "host",
+            0,
+            180,
+            &get_server_config("scale"),
+            1000,
+        );
+
+        assert!(gaps.is_empty());
+        assert_eq!(scale, 1.0);
+    }
+}
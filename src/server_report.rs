@@ -0,0 +1,209 @@
+use crate::common_certificate;
+use crate::common_certificate::Certificate;
+use crate::config::Config;
+use crate::db::Db;
+use crate::db_epoch::DbEpoch;
+use crate::db_record::DbRecord;
+use crate::db_statistics::DbStatistics;
+use crate::de::De;
+use crate::response_get_proof_of_computation::ProofOfComputationData;
+use crate::response_get_report::{ReportData, ReportGap, ResponseGetReport};
+use crate::server_config;
+use crate::server_config::ServerConfig;
+use crate::server_proof;
+use clap::ArgMatches;
+use std::time::SystemTime;
+
+pub fn get(
+    config: &Config,
+    database: &Db,
+    de: &De,
+    from: u64,
+    server_config: &ServerConfig,
+    to: u64,
+) -> ResponseGetReport {
+    let now: u64 = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let mut host_ids: Vec<&String> = database.iter().map(|(host_id, _)| host_id).collect();
+    host_ids.sort();
+
+    let mut data: Vec<ReportData> = vec![];
+
+    for host_id in host_ids {
+        let db_record: &DbRecord = database.get(host_id).unwrap();
+        let (_, gaps, _): (Vec<&DbStatistics>, Vec<(u64, u64)>, f64) =
+            server_proof::select_window(db_record, host_id, from, to, server_config, now);
+        let (db_epochs, windows): (Vec<&DbEpoch>, Vec<(u64, u64)>) =
+            get_windows(db_record, from, to);
+        let mut proof_of_computation: f64 = 0.0;
+        let mut sample_count: usize = 0;
+
+        for db_epoch in db_epochs {
+            proof_of_computation += db_epoch.proof_of_computation();
+            sample_count += db_epoch.data().len();
+        }
+
+        for (window_from, window_to) in windows {
+            let (statistics, _, scale): (Vec<&DbStatistics>, Vec<(u64, u64)>, f64) =
+                server_proof::select_window(
+                    db_record,
+                    host_id,
+                    window_from,
+                    window_to,
+                    server_config,
+                    now,
+                );
+            let (proof_of_computation_data, window_proof_of_computation): (
+                Vec<ProofOfComputationData>,
+                f64,
+            ) = server_proof::compute(&statistics, scale, server_config.scoring());
+
+            proof_of_computation += window_proof_of_computation;
+            sample_count += proof_of_computation_data.len();
+        }
+
+        data.push(ReportData::new(
+            gaps.into_iter()
+                .map(|(from, to)| ReportGap::new(from, to))
+                .collect(),
+            host_id.to_string(),
+            proof_of_computation,
+            sample_count,
+            0.0,
+        ));
+    }
+
+    let proof_of_computation: f64 = data
+        .iter()
+        .fold(0.0, |sum, d| sum + d.proof_of_computation());
+
+    if proof_of_computation > 0.0 {
+        for d in &mut data {
+            let share: f64 = d.proof_of_computation() / proof_of_computation;
+            d.set_share(share);
+        }
+    }
+
+    ResponseGetReport::new(
+        data,
+        from,
+        config.host_id().to_string(),
+        proof_of_computation,
+        de.public_key_string(),
+        to,
+    )
+}
+
+pub fn get_bounds(
+    epoch: Option<u64>,
+    from: Option<u64>,
+    to: Option<u64>,
+    epoch_length: u64,
+) -> Option<(Option<u64>, Option<u64>)> {
+    match (epoch, from, to) {
+        (Some(epoch), None, None) => {
+            let from: u64 = epoch.checked_mul(epoch_length)?;
+            let to: u64 = from.checked_add(epoch_length)?;
+            Some((Some(from), Some(to)))
+        }
+        (None, from, to) => Some((from, to)),
+        _ => None,
+    }
+}
+
+fn get_windows(db_record: &DbRecord, from: u64, to: u64) -> (Vec<&DbEpoch>, Vec<(u64, u64)>) {
+    let mut db_epochs: Vec<&DbEpoch> = db_record
+        .epochs()
+        .iter()
+        .filter(|db_epoch| *db_epoch.from() >= from && *db_epoch.to() <= to)
+        .collect();
+    db_epochs.sort_by_key(|db_epoch| *db_epoch.from());
+
+    let mut cursor: u64 = from;
+    let mut frozen: Vec<&DbEpoch> = vec![];
+    let mut windows: Vec<(u64, u64)> = vec![];
+
+    for db_epoch in db_epochs {
+        if *db_epoch.from() < cursor {
+            continue;
+        }
+
+        if *db_epoch.from() > cursor {
+            windows.push((cursor, *db_epoch.from()));
+        }
+
+        cursor = *db_epoch.to();
+        frozen.push(db_epoch);
+    }
+
+    if cursor < to {
+        windows.push((cursor, to));
+    }
+
+    (frozen, windows)
+}
+
+pub async fn run(
+    config: Config,
+    de: De,
+    report_parameters: &ArgMatches<'_>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let server_config: ServerConfig = server_config::parse(&config)?;
+    let mut database: Db = Db::new(config.data_dir().to_string(), de.clone());
+    database.restore()?;
+
+    let parameter = |name: &str| -> Result<Option<u64>, std::num::ParseIntError> {
+        report_parameters
+            .value_of(name)
+            .map(|value| value.parse())
+            .transpose()
+    };
+    let (from, to): (Option<u64>, Option<u64>) = get_bounds(
+        parameter("epoch")?,
+        parameter("from")?,
+        parameter("to")?,
+        (*server_config.epoch_length()).max(1),
+    )
+    .ok_or("--epoch can not be combined with --from or --to and must not overflow")?;
+    let now: u64 = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_secs();
+
+    let response_get_report: ResponseGetReport = get(
+        &config,
+        &database,
+        &de,
+        from.unwrap_or(0),
+        &server_config,
+        to.unwrap_or(now),
+    );
+    let body: String = serde_json::to_string(&response_get_report)?;
+    let certificate: Certificate = common_certificate::issue(&de, body);
+
+    println!("{}", serde_json::to_string_pretty(&certificate)?);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_bounds_returns_epoch_window() {
+        assert_eq!(
+            get_bounds(Some(2), None, None, 10),
+            Some((Some(20), Some(30)))
+        );
+        assert_eq!(get_bounds(None, Some(5), None, 10), Some((Some(5), None)));
+        assert_eq!(get_bounds(Some(2), Some(5), None, 10), None);
+    }
+
+    #[test]
+    fn get_bounds_rejects_overflowing_epoch() {
+        assert_eq!(get_bounds(Some(u64::MAX), None, None, 86400), None);
+        assert_eq!(get_bounds(Some(u64::MAX / 86400), None, None, 86400), None);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::common_metric;
+use crate::db_statistics::DbStatistics;
+use crate::request_post_statistics::RequestPostStatistics;
+use crate::server_config::ServerScoringConfig;
+
+pub fn cpu_usage(
+    request_post_statistics: &RequestPostStatistics,
+    scoring_config: &ServerScoringConfig,
+) -> Option<f32> {
+    if let Some(workload_cpu) =
+        common_metric::get_f64(request_post_statistics.metrics(), "process.workload_cpu")
+    {
+        return Some(workload_cpu as f32);
+    }
+
+    if *scoring_config.workload_cpu_only() {
+        return Some(0.0);
+    }
+
+    let cpu_usage: Option<f32> = request_post_statistics
+        .cpu_idle()
+        .map(|cpu_idle| 100.0 - cpu_idle);
+
+    match request_post_statistics.container() {
+        Some(container) if *request_post_statistics.containerized() => {
+            container.cpu_usage().or(cpu_usage)
+        }
+        _ => cpu_usage,
+    }
+}
+
+pub fn mem_used_percent(request_post_statistics: &RequestPostStatistics) -> Option<(f32, bool)> {
+    if *request_post_statistics.protocol_version() >= 2 {
+        return request_post_statistics
+            .mem_used_percent()
+            .map(|mem_used_percent| (mem_used_percent, false));
+    }
+
+    request_post_statistics
+        .mem_usage()
+        .filter(|mem_usage| *mem_usage > 0.0 && mem_usage.is_finite())
+        .map(|mem_usage| ((1.0 - mem_usage) * 100.0, true))
+}
+
+pub fn partial_proof_of_computation(
+    statistic: &DbStatistics,
+    scoring_config: &ServerScoringConfig,
+) -> f64 {
+    let mut cpu_usage: f64 = *statistic.cpu_usage() as f64 / 100.0;
+
+    if cpu_usage == 0.0 {
+        cpu_usage = 0.01;
+    }
+
+    let mut mem_usage: f64 = statistic.mem_used_percent().unwrap_or(0.0) as f64 / 100.0;
+
+    if mem_usage == 0.0 {
+        mem_usage = 0.01;
+    }
+
+    let mut mt_2_result_multiplier: f64 = 1.0 - statistic.mt_2_result();
+
+    if mt_2_result_multiplier <= 0.0 {
+        mt_2_result_multiplier = 0.01;
+    }
+
+    let mut mt_4_result_multiplier: f64 = 1.0 - statistic.mt_4_result();
+
+    if mt_4_result_multiplier <= 0.0 {
+        mt_4_result_multiplier = 0.01;
+    }
+
+    let mut mt_8_result_multiplier: f64 = 1.0 - statistic.mt_8_result();
+
+    if mt_8_result_multiplier <= 0.0 {
+        mt_8_result_multiplier = 0.01;
+    }
+
+    let mut st_result_multiplier: f64 = 1.0 - statistic.st_result();
+
+    if st_result_multiplier <= 0.0 {
+        st_result_multiplier = 0.01;
+    }
+
+    let mut sys_load_average_fifteen_multiplier: f64 =
+        statistic.sys_load_average_fifteen().unwrap_or(0.0) as f64;
+
+    if sys_load_average_fifteen_multiplier <= 0.0 {
+        sys_load_average_fifteen_multiplier = 0.01;
+    }
+
+    let mut sys_load_average_five_multiplier: f64 =
+        statistic.sys_load_average_five().unwrap_or(0.0) as f64;
+
+    if sys_load_average_five_multiplier <= 0.0 {
+        sys_load_average_five_multiplier = 0.01;
+    }
+
+    let mut sys_load_average_one_multiplier: f64 =
+        statistic.sys_load_average_one().unwrap_or(0.0) as f64;
+
+    if sys_load_average_one_multiplier <= 0.0 {
+        sys_load_average_one_multiplier = 0.01;
+    }
+
+    let cpu_cores_busy: Vec<f64> = statistic
+        .cpu_cores()
+        .iter()
+        .map(|cpu_core| cpu_core.busy() as f64 / 100.0)
+        .collect();
+    let mut cpu_core_balance_multiplier: f64 = 1.0;
+
+    if !cpu_cores_busy.is_empty() {
+        let cpu_cores_busy_max: f64 = cpu_cores_busy.iter().cloned().fold(0.0, f64::max);
+        let cpu_cores_busy_mean: f64 =
+            cpu_cores_busy.iter().sum::<f64>() / cpu_cores_busy.len() as f64;
+        cpu_core_balance_multiplier = 1.0
+            - scoring_config.cpu_core_balance_weight() * (cpu_cores_busy_max - cpu_cores_busy_mean);
+
+        if cpu_core_balance_multiplier <= 0.0 {
+            cpu_core_balance_multiplier = 0.01;
+        }
+    }
+
+    let disk_busy_ratio: f64 = statistic
+        .disk_io()
+        .iter()
+        .map(|disk_io| disk_io.busy_ratio())
+        .fold(0.0, f64::max);
+    let disk_io_multiplier: f64 = 1.0 + scoring_config.disk_io_weight() * disk_busy_ratio;
+    let network_throughput: f64 = statistic
+        .network_interfaces()
+        .iter()
+        .map(|network_interface| network_interface.bytes_per_second())
+        .sum();
+    let mut network_ratio: f64 = 0.0;
+
+    if *scoring_config.network_reference_throughput() > 0.0 {
+        network_ratio =
+            (network_throughput / scoring_config.network_reference_throughput()).min(1.0);
+    }
+
+    let network_multiplier: f64 = 1.0 + scoring_config.network_weight() * network_ratio;
+
+    cpu_usage
+        * mem_usage
+        * mt_2_result_multiplier
+        * mt_4_result_multiplier
+        * mt_8_result_multiplier
+        * st_result_multiplier
+        * sys_load_average_fifteen_multiplier
+        * sys_load_average_five_multiplier
+        * sys_load_average_one_multiplier
+        * cpu_core_balance_multiplier
+        * disk_io_multiplier
+        * network_multiplier
+        * statistic.sys_uptime().unwrap_or(0.0)
+}
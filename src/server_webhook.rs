@@ -0,0 +1,480 @@
+use crate::common_file;
+use crate::config::Config;
+use crate::de::De;
+use crate::info::Info;
+use crate::server_config::{ServerConfig, ServerWebhooksConfig};
+use hyper::{Body, Client, Method, Request};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+const DELIVERY_TIMEOUT: u64 = 10;
+const OUTBOX_MAX: usize = 10000;
+const RETRY_DELAY_MAX: u64 = 3600;
+const THRESHOLD_WINDOW: u64 = 60;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WebhookEvent {
+    data: Value,
+    event: String,
+    host_id: String,
+    id: String,
+    occurred_at: u64,
+}
+
+impl WebhookEvent {
+    pub fn new(data: Value, event: String, host_id: String, id: String, occurred_at: u64) -> Self {
+        Self {
+            data,
+            event,
+            host_id,
+            id,
+            occurred_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct WebhookDelivery {
+    attempts: u32,
+    event: WebhookEvent,
+    next_attempt: u64,
+    url: Option<String>,
+}
+
+#[derive(Debug)]
+struct WebhookOutbox {
+    deliveries: Vec<WebhookDelivery>,
+    path: String,
+}
+
+#[derive(Debug, Default)]
+struct WebhookCounters {
+    signature_failures: u64,
+    validation_rejections: u64,
+    window_start: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Webhooks {
+    counters: Arc<Mutex<WebhookCounters>>,
+    host_id: String,
+    notify: Arc<Notify>,
+    outbox: Arc<Mutex<WebhookOutbox>>,
+    server_config: Arc<RwLock<ServerConfig>>,
+}
+
+impl Webhooks {
+    pub fn emit(&self, event: &str, data: Value) {
+        let webhook_event: WebhookEvent = WebhookEvent::new(
+            data,
+            event.to_string(),
+            self.host_id.to_string(),
+            Uuid::new_v4().to_string(),
+            now(),
+        );
+        let mut outbox = self.outbox.lock().unwrap();
+
+        if outbox.deliveries.len() >= OUTBOX_MAX {
+            let delivery: WebhookDelivery = outbox.deliveries.remove(0);
+
+            warn!(
+                "Webhook outbox is full, dropping event = {}",
+                delivery.event.event
+            );
+        }
+
+        outbox.deliveries.push(WebhookDelivery {
+            attempts: 0,
+            event: webhook_event,
+            next_attempt: 0,
+            url: None,
+        });
+        save(&outbox.path, &outbox.deliveries);
+        drop(outbox);
+        self.notify.notify_one();
+    }
+
+    pub fn record_signature_failure(&self) {
+        let count: u64 = self.count(|counters| &mut counters.signature_failures);
+        let threshold: u64 = (*self
+            .server_config
+            .read()
+            .unwrap()
+            .webhooks()
+            .signature_failure_threshold())
+        .max(1);
+
+        if count == threshold {
+            self.emit(
+                "signature.failures_exceeded",
+                serde_json::json!({ "count": count, "window": THRESHOLD_WINDOW }),
+            );
+        }
+    }
+
+    pub fn record_validation_rejection(&self) {
+        let count: u64 = self.count(|counters| &mut counters.validation_rejections);
+        let threshold: u64 = (*self
+            .server_config
+            .read()
+            .unwrap()
+            .webhooks()
+            .validation_rejection_threshold())
+        .max(1);
+
+        if count == threshold {
+            self.emit(
+                "validation.rejections_exceeded",
+                serde_json::json!({ "count": count, "window": THRESHOLD_WINDOW }),
+            );
+        }
+    }
+
+    fn count(&self, counter: impl Fn(&mut WebhookCounters) -> &mut u64) -> u64 {
+        let mut counters = self.counters.lock().unwrap();
+        let now: u64 = now();
+
+        if now >= counters.window_start + THRESHOLD_WINDOW {
+            *counters = WebhookCounters {
+                signature_failures: 0,
+                validation_rejections: 0,
+                window_start: now,
+            };
+        }
+
+        let count: &mut u64 = counter(&mut counters);
+        *count += 1;
+        *count
+    }
+}
+
+pub fn get(config: &Config, server_config: Arc<RwLock<ServerConfig>>) -> Webhooks {
+    let outbox_path: String = format!(
+        "{dir}{file}",
+        dir = config.data_dir(),
+        file = "webhook_outbox.json"
+    );
+
+    Webhooks {
+        counters: Arc::new(Mutex::new(WebhookCounters::default())),
+        host_id: config.host_id().to_string(),
+        notify: Arc::new(Notify::new()),
+        outbox: Arc::new(Mutex::new(WebhookOutbox {
+            deliveries: restore(&outbox_path),
+            path: outbox_path,
+        })),
+        server_config,
+    }
+}
+
+pub async fn run(de: De, info: Info, webhooks: Webhooks) {
+    loop {
+        tokio::select! {
+            _ = webhooks.notify.notified() => {}
+            _ = tokio::time::sleep(Duration::from_secs(1)) => {}
+        }
+
+        let webhooks_config: ServerWebhooksConfig =
+            webhooks.server_config.read().unwrap().webhooks().clone();
+        let now: u64 = now();
+        let due: Vec<WebhookDelivery> = {
+            let mut outbox = webhooks.outbox.lock().unwrap();
+
+            if fan_out(&mut outbox.deliveries, webhooks_config.urls()) {
+                save(&outbox.path, &outbox.deliveries);
+            }
+
+            outbox
+                .deliveries
+                .iter()
+                .filter(|delivery| delivery.url.is_some() && delivery.next_attempt <= now)
+                .cloned()
+                .collect()
+        };
+
+        for delivery in due {
+            let url: String = delivery.url.clone().unwrap_or_default();
+            let result: Result<(), Box<dyn std::error::Error + Send + Sync>> =
+                deliver(&de, &info, &url, &delivery.event).await;
+            let mut outbox = webhooks.outbox.lock().unwrap();
+            let index: usize = match outbox.deliveries.iter().position(|pending| {
+                pending.event.id == delivery.event.id && pending.url == delivery.url
+            }) {
+                Some(index) => index,
+                None => continue,
+            };
+
+            match result {
+                Ok(()) => {
+                    info!(
+                        "Delivered webhook event = {} to {}",
+                        delivery.event.event, url
+                    );
+                    outbox.deliveries.remove(index);
+                }
+                Err(x) => {
+                    let pending: &mut WebhookDelivery = &mut outbox.deliveries[index];
+                    pending.attempts += 1;
+                    pending.next_attempt =
+                        now + (1u64 << pending.attempts.min(12)).min(RETRY_DELAY_MAX);
+
+                    warn!(
+                        "Webhook event = {} to {} failed (attempt {}): {}",
+                        pending.event.event, url, pending.attempts, x
+                    );
+
+                    if pending.attempts >= *webhooks_config.max_attempts() {
+                        warn!(
+                            "Dropping webhook event = {} to {} after {} attempts",
+                            pending.event.event, url, pending.attempts
+                        );
+                        outbox.deliveries.remove(index);
+                    }
+                }
+            }
+
+            save(&outbox.path, &outbox.deliveries);
+        }
+    }
+}
+
+fn fan_out(deliveries: &mut Vec<WebhookDelivery>, urls: &[String]) -> bool {
+    if urls.is_empty() || deliveries.iter().all(|delivery| delivery.url.is_some()) {
+        return false;
+    }
+
+    for delivery in std::mem::take(deliveries) {
+        match delivery.url {
+            Some(_) => deliveries.push(delivery),
+            None => {
+                for url in urls {
+                    deliveries.push(WebhookDelivery {
+                        url: Some(url.to_string()),
+                        ..delivery.clone()
+                    });
+                }
+            }
+        }
+    }
+
+    true
+}
+
+async fn deliver(
+    de: &De,
+    info: &Info,
+    url: &str,
+    webhook_event: &WebhookEvent,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let body: String = serde_json::to_string(webhook_event)?;
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(url)
+        .header("content-type", "application/json")
+        .header("signature", de.sign(&body))
+        .header("user-agent", format!("{}/{}", info.name(), info.version()))
+        .body(Body::from(body))?;
+    let response = tokio::time::timeout(
+        Duration::from_secs(DELIVERY_TIMEOUT),
+        Client::new().request(request),
+    )
+    .await
+    .map_err(|_| format!("Timed out after {} seconds", DELIVERY_TIMEOUT))??;
+
+    if !response.status().is_success() {
+        return Err(format!("HTTP status {}", response.status()).into());
+    }
+
+    Ok(())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn restore(outbox_path: &str) -> Vec<WebhookDelivery> {
+    if !Path::new(outbox_path).exists() {
+        return vec![];
+    }
+
+    match fs::read_to_string(outbox_path)
+        .map_err(|x| x.to_string())
+        .and_then(|contents| serde_json::from_str(&contents).map_err(|x| x.to_string()))
+    {
+        Ok(outbox) => outbox,
+        Err(x) => {
+            warn!("Can not restore webhook outbox: {}", x);
+            vec![]
+        }
+    }
+}
+
+fn save(outbox_path: &str, outbox: &[WebhookDelivery]) {
+    if let Err(x) = common_file::write_atomic(outbox_path, &serde_json::to_vec(outbox).unwrap()) {
+        warn!("Can not save webhook outbox: {}", x);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Response, Server, StatusCode};
+    use rand::rngs::OsRng;
+    use rsa::{RsaPrivateKey, RsaPublicKey};
+    use serde_json::json;
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+    use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+    type Received = (String, String);
+
+    fn get_de() -> De {
+        let private_key: RsaPrivateKey = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let public_key: RsaPublicKey = RsaPublicKey::from(&private_key);
+
+        De::new(None, private_key, public_key)
+    }
+
+    fn get_receiver(statuses: Vec<StatusCode>) -> (String, UnboundedReceiver<Received>) {
+        let (sender, receiver): (UnboundedSender<Received>, UnboundedReceiver<Received>) =
+            mpsc::unbounded_channel();
+        let statuses: Arc<Mutex<Vec<StatusCode>>> = Arc::new(Mutex::new(statuses));
+        let service = make_service_fn(move |_| {
+            let sender: UnboundedSender<Received> = sender.clone();
+            let statuses: Arc<Mutex<Vec<StatusCode>>> = statuses.clone();
+
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                    let sender: UnboundedSender<Received> = sender.clone();
+                    let statuses: Arc<Mutex<Vec<StatusCode>>> = statuses.clone();
+
+                    async move {
+                        let signature: String = request
+                            .headers()
+                            .get("signature")
+                            .and_then(|signature| signature.to_str().ok())
+                            .unwrap_or_default()
+                            .to_string();
+                        let body = hyper::body::to_bytes(request.into_body()).await.unwrap();
+                        sender
+                            .send((signature, String::from_utf8(body.to_vec()).unwrap()))
+                            .unwrap();
+                        let mut statuses = statuses.lock().unwrap();
+                        let status: StatusCode = if statuses.is_empty() {
+                            StatusCode::OK
+                        } else {
+                            statuses.remove(0)
+                        };
+
+                        Ok::<_, Infallible>(
+                            Response::builder()
+                                .status(status)
+                                .body(Body::empty())
+                                .unwrap(),
+                        )
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(service);
+        let url: String = format!("http://{}/events", server.local_addr());
+        tokio::spawn(server);
+
+        (url, receiver)
+    }
+
+    async fn wait_for_outbox(outbox_path: &str, expected: impl Fn(&[WebhookDelivery]) -> bool) {
+        for _ in 0..100 {
+            if expected(&restore(outbox_path)) {
+                return;
+            }
+
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        panic!("Outbox at {} never reached the expected state", outbox_path);
+    }
+
+    #[tokio::test]
+    async fn deliver_posts_signed_event() {
+        let de: De = get_de();
+        let (url, mut receiver): (String, UnboundedReceiver<Received>) = get_receiver(vec![]);
+        let webhook_event: WebhookEvent = WebhookEvent::new(
+            json!({ "host_id": "a" }),
+            String::from("host.enrolled"),
+            String::from("server"),
+            String::from("1"),
+            1,
+        );
+
+        deliver(&de, &Info::new(), &url, &webhook_event)
+            .await
+            .unwrap();
+        let (signature, body): (String, String) = receiver.recv().await.unwrap();
+
+        assert_eq!(body, serde_json::to_string(&webhook_event).unwrap());
+        assert!(de::check_sign(&body, &de.public_key_string(), &signature));
+    }
+
+    #[tokio::test]
+    async fn run_retries_failed_delivery_from_outbox() {
+        let (url, mut receiver): (String, UnboundedReceiver<Received>) =
+            get_receiver(vec![StatusCode::INTERNAL_SERVER_ERROR]);
+        let outbox_path: String = format!(
+            "{}/auditor_webhook_outbox_{}.json",
+            std::env::temp_dir().display(),
+            std::process::id()
+        );
+        let server_config: ServerConfig = serde_json::from_value(json!({
+            "address": "127.0.0.1",
+            "port": 8081,
+            "webhooks": { "urls": [url] },
+        }))
+        .unwrap();
+        let webhooks: Webhooks = Webhooks {
+            counters: Arc::new(Mutex::new(WebhookCounters::default())),
+            host_id: String::from("server"),
+            notify: Arc::new(Notify::new()),
+            outbox: Arc::new(Mutex::new(WebhookOutbox {
+                deliveries: vec![],
+                path: outbox_path.to_string(),
+            })),
+            server_config: Arc::new(RwLock::new(server_config)),
+        };
+
+        webhooks.emit("host.enrolled", json!({ "host_id": "a" }));
+        assert_eq!(restore(&outbox_path).len(), 1);
+
+        let task = tokio::spawn(run(get_de(), Info::new(), webhooks));
+        let timeout: Duration = Duration::from_secs(10);
+        let (_, first): (String, String) = tokio::time::timeout(timeout, receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        wait_for_outbox(&outbox_path, |deliveries| {
+            deliveries.len() == 1 && deliveries[0].attempts == 1
+        })
+        .await;
+        let (_, second): (String, String) = tokio::time::timeout(timeout, receiver.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        wait_for_outbox(&outbox_path, |deliveries| deliveries.is_empty()).await;
+        task.abort();
+        fs::remove_file(&outbox_path).unwrap();
+
+        assert_eq!(first, second);
+    }
+}